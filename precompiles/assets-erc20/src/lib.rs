@@ -31,8 +31,8 @@ use frame_support::{
 };
 use pallet_evm::{AddressMapping, PrecompileSet};
 use precompile_utils::{
-    keccak256, succeed, Address, Bytes, EvmData, EvmDataWriter, EvmResult, FunctionModifier,
-    LogExt, LogsBuilder, PrecompileHandleExt, RuntimeHelper,
+    keccak256, succeed, Address, Bytes, EvmData, EvmDataWriter, EvmResult, ExternalCostHandle,
+    FunctionModifier, GasOnlyHandle, LogExt, LogsBuilder, PrecompileHandleExt, RuntimeHelper,
 };
 use sp_runtime::traits::{Bounded, Zero};
 
@@ -124,6 +124,8 @@ where
     <<Runtime as frame_system::Config>::RuntimeCall as Dispatchable>::RuntimeOrigin: OriginTrait,
 {
     fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<EvmResult<PrecompileOutput>> {
+        // The EVM handle doesn't meter proof size, which is thus charged as gas.
+        let handle = &mut GasOnlyHandle::new(handle);
         let address = handle.code_address();
 
         if let Some(asset_id) = Runtime::address_to_asset_id(address) {
@@ -265,7 +267,7 @@ where
 
     fn approve(
         asset_id: AssetIdOf<Runtime, Instance>,
-        handle: &mut impl PrecompileHandle,
+        handle: &mut impl ExternalCostHandle,
     ) -> EvmResult<PrecompileOutput> {
         handle.record_log_costs_manual(3, 32)?;
 
@@ -280,7 +282,7 @@ where
 
     fn increase_allowance(
         asset_id: AssetIdOf<Runtime, Instance>,
-        handle: &mut impl PrecompileHandle,
+        handle: &mut impl ExternalCostHandle,
     ) -> EvmResult<PrecompileOutput> {
         handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
 
@@ -302,7 +304,7 @@ where
 
     fn decrease_allowance(
         asset_id: AssetIdOf<Runtime, Instance>,
-        handle: &mut impl PrecompileHandle,
+        handle: &mut impl ExternalCostHandle,
     ) -> EvmResult<PrecompileOutput> {
         handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
 
@@ -323,7 +325,7 @@ where
         Self::_approve(handle, asset_id, spender, amount.saturating_sub(subtracted_value))
     }
 
-    fn _approve(handle: &mut impl PrecompileHandle, asset_id: AssetIdOf<Runtime, Instance>, spender: H160, amount: U256) -> EvmResult<PrecompileOutput> {
+    fn _approve(handle: &mut impl ExternalCostHandle, asset_id: AssetIdOf<Runtime, Instance>, spender: H160, amount: U256) -> EvmResult<PrecompileOutput> {
         {
             let origin = Runtime::AddressMapping::into_account_id(handle.context().caller);
             let spender: Runtime::AccountId = Runtime::AddressMapping::into_account_id(spender);
@@ -373,7 +375,7 @@ where
 
     fn transfer(
         asset_id: AssetIdOf<Runtime, Instance>,
-        handle: &mut impl PrecompileHandle,
+        handle: &mut impl ExternalCostHandle,
     ) -> EvmResult<PrecompileOutput> {
         handle.record_log_costs_manual(3, 32)?;
 
@@ -414,7 +416,7 @@ where
 
     fn transfer_from(
         asset_id: AssetIdOf<Runtime, Instance>,
-        handle: &mut impl PrecompileHandle,
+        handle: &mut impl ExternalCostHandle,
     ) -> EvmResult<PrecompileOutput> {
         handle.record_log_costs_manual(3, 32)?;
 
@@ -535,7 +537,7 @@ where
 
     fn mint(
         asset_id: AssetIdOf<Runtime, Instance>,
-        handle: &mut impl PrecompileHandle,
+        handle: &mut impl ExternalCostHandle,
     ) -> EvmResult<PrecompileOutput> {
         let mut input = handle.read_input()?;
         input.expect_arguments(2)?;
@@ -562,7 +564,7 @@ where
 
     fn burn(
         asset_id: AssetIdOf<Runtime, Instance>,
-        handle: &mut impl PrecompileHandle,
+        handle: &mut impl ExternalCostHandle,
     ) -> EvmResult<PrecompileOutput> {
         let mut input = handle.read_input()?;
         input.expect_arguments(2)?;
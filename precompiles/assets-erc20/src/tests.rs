@@ -22,7 +22,7 @@ use std::str::from_utf8;
 use crate::mock::*;
 use crate::*;
 
-use precompile_utils::{testing::*, EvmDataWriter, LogsBuilder};
+use precompile_utils::{encode_revert_reason, testing::*, EvmDataWriter, LogsBuilder};
 use sha3::{Digest, Keccak256};
 
 fn precompiles() -> Erc20AssetsPrecompileSet<Runtime> {
@@ -42,7 +42,9 @@ fn selector_less_than_four_bytes() {
         // This selector is only three bytes long when four are required.
         precompiles()
             .prepare_test(Account::Alice, Account::AssetId(0u128), vec![1u8, 2u8, 3u8])
-            .execute_reverts(|output| output == b"tried to parse selector out of bounds");
+            .execute_reverts(|output| {
+                output == encode_revert_reason("tried to parse selector out of bounds")
+            });
     });
 }
 
@@ -63,7 +65,7 @@ fn no_selector_exists_but_length_is_right() {
                 Account::AssetId(0u128),
                 vec![1u8, 2u8, 3u8, 4u8],
             )
-            .execute_reverts(|output| output == b"unknown selector");
+            .execute_reverts(|output| output == encode_revert_reason("unknown selector"));
     });
 }
 
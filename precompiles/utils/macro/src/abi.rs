@@ -0,0 +1,106 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generation of Solidity ABI JSON from function signatures.
+
+use crate::check_signature;
+
+/// ABI JSON entry of the function with provided canonical signature and return types (such
+/// as `uint256,bool`). Inputs and outputs are unnamed.
+pub fn function_abi(
+    signature: &str,
+    returns: &str,
+    state_mutability: &str,
+) -> Result<String, &'static str> {
+    check_signature(signature)?;
+
+    let args_start = signature.find('(').expect("checked by check_signature");
+    let name = &signature[..args_start];
+    let args = &signature[args_start + 1..signature.len() - 1];
+
+    Ok(format!(
+        r#"{{"type":"function","name":"{}","inputs":[{}],"outputs":[{}],"stateMutability":"{}"}}"#,
+        name,
+        params_abi(args)?,
+        params_abi(returns)?,
+        state_mutability
+    ))
+}
+
+fn params_abi(args: &str) -> Result<String, &'static str> {
+    Ok(split_args(args)
+        .into_iter()
+        .map(param_abi)
+        .collect::<Result<Vec<_>, _>>()?
+        .join(","))
+}
+
+fn param_abi(ty: &str) -> Result<String, &'static str> {
+    if !ty.starts_with('(') {
+        return Ok(format!(r#"{{"name":"","type":"{}"}}"#, ty));
+    }
+
+    let mut depth = 0usize;
+    let close = ty
+        .char_indices()
+        .find_map(|(i, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => (),
+            }
+            if depth == 0 {
+                Some(i)
+            } else {
+                None
+            }
+        })
+        .ok_or("function signature has unbalanced parentheses")?;
+
+    Ok(format!(
+        r#"{{"name":"","type":"tuple{}","components":[{}]}}"#,
+        &ty[close + 1..],
+        params_abi(&ty[1..close])?
+    ))
+}
+
+/// Split arguments on top-level commas.
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    if !args.is_empty() {
+        parts.push(&args[start..]);
+    }
+
+    parts
+}
@@ -0,0 +1,122 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `dispatch!` implementation.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Expr, Ident, Path, Token,
+};
+
+/// `dispatch!(handle, Action { Variant => handler, ... })`
+pub struct Dispatch {
+    handle: Expr,
+    selector: Path,
+    arms: Punctuated<Arm, Token![,]>,
+}
+
+/// `Variant => handler`
+struct Arm {
+    attrs: Vec<Attribute>,
+    variant: Ident,
+    handler: Expr,
+}
+
+impl Parse for Dispatch {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let handle = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let selector = input.parse()?;
+
+        let content;
+        braced!(content in input);
+        let arms = content.parse_terminated(Arm::parse)?;
+
+        // Allow a trailing comma after the arms.
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(Self {
+            handle,
+            selector,
+            arms,
+        })
+    }
+}
+
+impl Parse for Arm {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let variant = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let handler = input.parse()?;
+
+        Ok(Self {
+            attrs,
+            variant,
+            handler,
+        })
+    }
+}
+
+pub fn expand(input: Dispatch) -> syn::Result<TokenStream> {
+    let Dispatch {
+        handle,
+        selector,
+        arms,
+    } = input;
+
+    let mut variants = vec![];
+    let mut handlers = vec![];
+    for arm in arms {
+        // The modifiers are declared once on the selector enum, to not disagree with its ABI.
+        if let Some(attr) = arm.attrs.first() {
+            return Err(syn::Error::new(
+                attr.span(),
+                "function modifiers must be declared on the variants of the selector enum",
+            ));
+        }
+
+        variants.push(arm.variant);
+        handlers.push(arm.handler);
+    }
+
+    Ok(quote! {{
+        let __handle = &mut *(#handle);
+        let __selector: #selector =
+            ::precompile_utils::PrecompileHandleExt::read_selector(&*__handle)?;
+
+        ::precompile_utils::PrecompileHandleExt::check_function_modifier(
+            &*__handle,
+            __selector.modifier(),
+        )?;
+
+        match __selector {
+            #(
+                #selector::#variants => (#handlers)(__handle),
+            )*
+        }
+    }})
+}
@@ -0,0 +1,113 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `#[derive(EvmEvent)]` implementation.
+
+use crate::solidity::{is_value_type, solidity_type};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use sha3::{Digest, Keccak256};
+use syn::{spanned::Spanned, Data, DeriveInput, Fields};
+
+/// Maximum amount of indexed fields, the first topic being used by the event signature.
+const MAX_INDEXED: usize = 3;
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "EvmEvent can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "EvmEvent can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut types = vec![];
+    let mut topics = vec![];
+    let mut data = vec![];
+    for field in fields {
+        let field_ident = field.ident.as_ref().expect("fields are named");
+        let ty = solidity_type(&field.ty)?;
+
+        if field.attrs.iter().any(|attr| attr.path.is_ident("indexed")) {
+            if !is_value_type(&ty) {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "only value types can be indexed",
+                ));
+            }
+
+            topics.push(field_ident);
+        } else {
+            data.push(field_ident);
+        }
+
+        types.push(ty);
+    }
+
+    if topics.len() > MAX_INDEXED {
+        return Err(syn::Error::new(
+            input.span(),
+            format!(
+                "an event can't have more than {} indexed fields",
+                MAX_INDEXED
+            ),
+        ));
+    }
+
+    let signature = format!("{}({})", ident, types.join(","));
+    let topic: Vec<u8> = Keccak256::digest(signature.as_bytes()).to_vec();
+    let log_fn = format_ident!("log{}", topics.len() + 1);
+    let field_idents = fields.iter().map(|field| &field.ident);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::precompile_utils::EvmEvent for #ident #ty_generics #where_clause {
+            const SIGNATURE: &'static str = #signature;
+            const TOPIC: ::precompile_utils::__private::H256 =
+                ::precompile_utils::__private::H256([#(#topic),*]);
+
+            fn to_log(
+                self,
+                address: ::precompile_utils::__private::H160,
+            ) -> ::precompile_utils::__private::Log {
+                let Self { #(#field_idents),* } = self;
+
+                ::precompile_utils::LogsBuilder::new(address).#log_fn(
+                    Self::TOPIC,
+                    #(::precompile_utils::encode_topic(#topics),)*
+                    ::precompile_utils::EvmDataWriter::new()
+                        #(.write(#data))*
+                        .build(),
+                )
+            }
+        }
+    })
+}
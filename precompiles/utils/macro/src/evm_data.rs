@@ -0,0 +1,108 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `#[derive(EvmData)]` implementation.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{spanned::Spanned, Data, DeriveInput, Fields};
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "EvmData can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "EvmData can only be derived for structs",
+            ))
+        }
+    };
+
+    let field_idents: Vec<_> = fields
+        .iter()
+        .map(|field| field.ident.as_ref().expect("fields are named"))
+        .collect();
+    let field_names: Vec<_> = field_idents.iter().map(|ident| ident.to_string()).collect();
+    let field_types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let fields_count = field_types.len();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Fields are encoded as a tuple, which is behind a pointer if any field is dynamic.
+    Ok(quote! {
+        impl #impl_generics ::precompile_utils::EvmData for #ident #ty_generics #where_clause {
+            fn has_static_size() -> bool {
+                true #(&& <#field_types as ::precompile_utils::EvmData>::has_static_size())*
+            }
+
+            fn solidity_type() -> ::core::option::Option<::precompile_utils::__private::String> {
+                let types: [::precompile_utils::__private::String; #fields_count] = [
+                    #(<#field_types as ::precompile_utils::EvmData>::solidity_type()?),*
+                ];
+                ::core::option::Option::Some(
+                    ::precompile_utils::__private::format!("({})", types.join(",")),
+                )
+            }
+
+            fn read(
+                reader: &mut ::precompile_utils::EvmDataReader,
+            ) -> ::precompile_utils::EvmResult<Self> {
+                if !<Self as ::precompile_utils::EvmData>::has_static_size() {
+                    reader.read_pointed(|reader| {
+                        Ok(Self {
+                            #(#field_idents: reader.read_field(#field_names)?,)*
+                        })
+                    })
+                } else {
+                    Ok(Self {
+                        #(#field_idents: reader.read_field(#field_names)?,)*
+                    })
+                }
+            }
+
+            fn write(writer: &mut ::precompile_utils::EvmDataWriter, value: Self) {
+                let Self { #(#field_idents),* } = value;
+
+                if !<Self as ::precompile_utils::EvmData>::has_static_size() {
+                    let mut inner_writer = ::precompile_utils::EvmDataWriter::new();
+                    #(
+                        <#field_types as ::precompile_utils::EvmData>::write(
+                            &mut inner_writer,
+                            #field_idents,
+                        );
+                    )*
+                    writer.write_pointer(inner_writer.build());
+                } else {
+                    #(
+                        <#field_types as ::precompile_utils::EvmData>::write(writer, #field_idents);
+                    )*
+                }
+            }
+        }
+    })
+}
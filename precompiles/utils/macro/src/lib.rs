@@ -25,7 +25,15 @@ use proc_macro2::Literal;
 use quote::{quote, quote_spanned};
 use sha3::{Digest, Keccak256};
 use std::convert::TryInto;
-use syn::{parse_macro_input, spanned::Spanned, Expr, ExprLit, Ident, ItemEnum, Lit, LitStr};
+use syn::{
+    parse_macro_input, spanned::Spanned, DeriveInput, Expr, ExprLit, Ident, ItemEnum, Lit, LitStr,
+};
+
+mod abi;
+mod dispatch;
+mod event;
+mod evm_data;
+mod solidity;
 
 struct Bytes(Vec<u8>);
 
@@ -61,6 +69,72 @@ pub fn keccak256(input: TokenStream) -> TokenStream {
     quote!(#eval_ts).into()
 }
 
+/// Compute the selector (first 4 bytes of the Keccak256 hash, as a `u32`) of a canonical
+/// Solidity function signature.
+///
+/// Usage:
+///
+/// ```ignore
+/// const TRANSFER: u32 = function_selector!("transfer(address,uint256)"); // 0xa9059cbb
+/// ```
+#[proc_macro]
+pub fn function_selector(input: TokenStream) -> TokenStream {
+    let lit_str = parse_macro_input!(input as LitStr);
+    let signature = lit_str.value();
+
+    if let Err(e) = check_signature(&signature) {
+        return syn::Error::new(lit_str.span(), e).to_compile_error().into();
+    }
+
+    let selector = u32::from_be_bytes(
+        Keccak256::digest(signature.as_ref())[..4]
+            .try_into()
+            .unwrap(),
+    );
+    let selector = Literal::u32_suffixed(selector);
+
+    quote!(#selector).into()
+}
+
+/// Check that a function signature is canonical: no whitespace, and a name followed by
+/// balanced parentheses.
+fn check_signature(signature: &str) -> Result<(), &'static str> {
+    if signature.chars().any(char::is_whitespace) {
+        return Err("function signature must not contain whitespace");
+    }
+
+    let args_start = signature
+        .find('(')
+        .ok_or("function signature must contain parentheses")?;
+
+    if args_start == 0 {
+        return Err("function signature must start with the function name");
+    }
+
+    let mut depth = 0usize;
+    for (i, c) in signature[args_start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or("function signature has unbalanced parentheses")?;
+
+                if depth == 0 && args_start + i + 1 != signature.len() {
+                    return Err("function signature must end after its arguments");
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if depth != 0 {
+        return Err("function signature has unbalanced parentheses");
+    }
+
+    Ok(())
+}
+
 /// This macro allows to associate to each variant of an enumeration a discriminant (of type u32
 /// whose value corresponds to the first 4 bytes of the Hash Keccak256 of the character string
 ///indicated by the user of this macro.
@@ -75,6 +149,23 @@ pub fn keccak256(input: TokenStream) -> TokenStream {
 /// }
 /// ```
 ///
+/// Variants can be annotated with `#[view]` or `#[payable]`, which is reflected in the
+/// `stateMutability` of the Solidity ABI JSON exposed as `Action::ABI`, and in the
+/// `FunctionModifier` returned by `Action::modifier`.
+///
+/// The return types of a function can be declared with `#[returns("uint256,bool")]`, which
+/// are the `outputs` of its ABI JSON. `Action::return_builder` creates a `ReturnBuilder`
+/// checking the written values against them.
+///
+/// A variant can also be given an explicit selector as an integer literal, in which case it is
+/// not part of the ABI JSON. Variants with the same selector are rejected.
+///
+/// Renamed functions can keep their previous selectors with `#[alias("oldName(uint256)")]`
+/// (or an explicit selector). Aliases decode to the variant, but are not part of the ABI JSON.
+///
+/// All the selectors are listed in `Action::SELECTORS` along with their signature and function
+/// modifier, which can be exported for audits.
+///
 /// Extanded to:
 ///
 /// ```rust
@@ -100,27 +191,58 @@ pub fn generate_function_selector(_: TokenStream, input: TokenStream) -> TokenSt
 
     let mut ident_expressions: Vec<Ident> = vec![];
     let mut variant_expressions: Vec<Expr> = vec![];
+    let mut variant_attrs: Vec<proc_macro2::TokenStream> = vec![];
+    let mut selectors: Vec<(u32, usize)> = vec![];
+    let mut abi_entries: Vec<String> = vec![];
+    let mut selector_rows: Vec<proc_macro2::TokenStream> = vec![];
+    let mut modifiers: Vec<proc_macro2::TokenStream> = vec![];
+    let mut returns_list: Vec<String> = vec![];
+    let mut builder_returns: Vec<String> = vec![];
     for variant in variants {
-        match variant.discriminant {
-            Some((_, Expr::Lit(ExprLit { lit, .. }))) => {
-                if let Lit::Str(lit_str) = lit {
+        let state_mutability = match state_mutability(&variant.attrs) {
+            Ok(state_mutability) => state_mutability,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        let modifier = match state_mutability {
+            "view" => quote!(::precompile_utils::FunctionModifier::View),
+            "payable" => quote!(::precompile_utils::FunctionModifier::Payable),
+            _ => quote!(::precompile_utils::FunctionModifier::NonPayable),
+        };
+        let returns = match returns(&variant.attrs) {
+            Ok(returns) => returns,
+            Err(e) => return e.to_compile_error().into(),
+        };
+
+        let (selector, signature) = match variant.discriminant {
+            Some((_, Expr::Lit(ExprLit { lit, .. }))) => match lit {
+                Lit::Str(lit_str) => {
+                    match abi::function_abi(&lit_str.value(), &returns, state_mutability) {
+                        Ok(entry) => abi_entries.push(entry),
+                        Err(e) => {
+                            return syn::Error::new(lit_str.span(), e).to_compile_error().into()
+                        }
+                    }
+
                     let selector = u32::from_be_bytes(
                         Keccak256::digest(lit_str.value().as_ref())[..4]
                             .try_into()
                             .unwrap(),
                     );
-                    ident_expressions.push(variant.ident);
-                    variant_expressions.push(Expr::Lit(ExprLit {
-                        lit: Lit::Verbatim(Literal::u32_suffixed(selector)),
-                        attrs: Default::default(),
-                    }));
-                } else {
+
+                    (selector, lit_str.value())
+                }
+                // Explicit selector, which doesn't have a signature to be part of the ABI.
+                Lit::Int(lit_int) => match lit_int.base10_parse::<u32>() {
+                    Ok(selector) => (selector, String::new()),
+                    Err(e) => return e.to_compile_error().into(),
+                },
+                lit => {
                     return quote_spanned! {
-                        lit.span() => compile_error("Expected literal string");
+                        lit.span() => compile_error("Expected literal string or integer");
                     }
                     .into();
                 }
-            }
+            },
             Some((_eg, expr)) => {
                 return quote_spanned! {
                     expr.span() => compile_error("Expected literal");
@@ -133,18 +255,276 @@ pub fn generate_function_selector(_: TokenStream, input: TokenStream) -> TokenSt
                 }
                 .into()
             }
+        };
+
+        let aliases = match aliases(&variant.attrs) {
+            Ok(aliases) => aliases,
+            Err(e) => return e.to_compile_error().into(),
+        };
+
+        // Colliding selectors would silently dispatch to the first variant.
+        let index = ident_expressions.len();
+        let rows = core::iter::once((selector, signature)).chain(aliases.iter().cloned());
+        for (selector, signature) in rows {
+            if let Some((_, other)) = selectors.iter().find(|(s, _)| *s == selector) {
+                let other = ident_expressions.get(*other).unwrap_or(&variant.ident);
+                return syn::Error::new(
+                    variant.ident.span(),
+                    format!(
+                        "function selector collision between `{}` and `{}` ({:#010x})",
+                        other, variant.ident, selector
+                    ),
+                )
+                .to_compile_error()
+                .into();
+            }
+
+            selectors.push((selector, index));
+            selector_rows.push(quote! {
+                (#selector, #signature, #modifier)
+            });
         }
+
+        variant_attrs.push(if aliases.is_empty() {
+            quote! {}
+        } else {
+            let aliases = aliases
+                .into_iter()
+                .map(|(selector, _)| Literal::u32_suffixed(selector));
+            quote! { #[num_enum(alternatives = [#(#aliases),*])] }
+        });
+        modifiers.push(modifier);
+        // Enclosed in parentheses for `ReturnBuilder::new` to not strip the ones of a single
+        // tuple return value.
+        builder_returns.push(format!("({})", returns));
+        returns_list.push(returns);
+        ident_expressions.push(variant.ident);
+        variant_expressions.push(Expr::Lit(ExprLit {
+            lit: Lit::Verbatim(Literal::u32_suffixed(selector)),
+            attrs: Default::default(),
+        }));
     }
 
+    let abi = format!("[{}]", abi_entries.join(","));
+
     (quote! {
         #(#attrs)*
         #[derive(num_enum::TryFromPrimitive, num_enum::IntoPrimitive)]
         #[repr(u32)]
         #vis #enum_token #ident {
             #(
+                #variant_attrs
                 #ident_expressions = #variant_expressions,
             )*
         }
+
+        impl #ident {
+            /// Solidity ABI JSON of the functions.
+            pub const ABI: &'static str = #abi;
+
+            /// Selector, signature and modifier of every function, including aliases.
+            /// Explicit selectors have an empty signature.
+            pub const SELECTORS: &'static [(u32, &'static str, ::precompile_utils::FunctionModifier)] = &[
+                #(#selector_rows),*
+            ];
+
+            /// Modifier of the function, from the `#[view]` or `#[payable]` attribute of its
+            /// variant.
+            pub fn modifier(&self) -> ::precompile_utils::FunctionModifier {
+                match *self {
+                    #(Self::#ident_expressions => #modifiers,)*
+                }
+            }
+
+            /// Solidity return types of the function, such as `uint256,bool`, from the
+            /// `#[returns(..)]` attribute of its variant. Empty if it returns nothing.
+            pub fn returns(&self) -> &'static str {
+                match *self {
+                    #(Self::#ident_expressions => #returns_list,)*
+                }
+            }
+
+            /// Builder of the output of the function, checking in debug builds that the
+            /// written values match its return types.
+            pub fn return_builder(&self) -> ::precompile_utils::ReturnBuilder {
+                ::precompile_utils::ReturnBuilder::new(match *self {
+                    #(Self::#ident_expressions => #builder_returns,)*
+                })
+            }
+        }
     })
     .into()
 }
+
+/// State mutability of a function, from the `#[view]` or `#[payable]` attribute of its
+/// variant. Defaults to `nonpayable`.
+fn state_mutability(attrs: &[syn::Attribute]) -> syn::Result<&'static str> {
+    let mut state_mutability = None;
+
+    for attr in attrs {
+        let modifier = if attr.path.is_ident("view") {
+            "view"
+        } else if attr.path.is_ident("payable") {
+            "payable"
+        } else {
+            continue;
+        };
+
+        if state_mutability.replace(modifier).is_some() {
+            return Err(syn::Error::new(
+                attr.span(),
+                "a function can't have multiple modifiers",
+            ));
+        }
+    }
+
+    Ok(state_mutability.unwrap_or("nonpayable"))
+}
+
+/// Return types of a function, from the `#[returns(..)]` attribute of its variant.
+/// Empty if the function returns nothing.
+fn returns(attrs: &[syn::Attribute]) -> syn::Result<String> {
+    let mut returns = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("returns")) {
+        let lit_str: LitStr = attr.parse_args()?;
+
+        // The return types are checked like the arguments of a signature.
+        check_signature(&format!("returns({})", lit_str.value()))
+            .map_err(|e| syn::Error::new(lit_str.span(), e))?;
+
+        if returns.replace(lit_str.value()).is_some() {
+            return Err(syn::Error::new(
+                attr.span(),
+                "a function can't have multiple `returns` attributes",
+            ));
+        }
+    }
+
+    Ok(returns.unwrap_or_default())
+}
+
+/// Additional selectors of a function, from the `#[alias(..)]` attributes of its variant.
+/// Aliases are either function signatures or explicit selectors.
+/// Explicit selectors have an empty signature.
+fn aliases(attrs: &[syn::Attribute]) -> syn::Result<Vec<(u32, String)>> {
+    let mut aliases = vec![];
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("alias")) {
+        let lits = attr.parse_args_with(
+            syn::punctuated::Punctuated::<Lit, syn::Token![,]>::parse_terminated,
+        )?;
+
+        for lit in lits {
+            aliases.push(match lit {
+                Lit::Str(lit_str) => {
+                    check_signature(&lit_str.value())
+                        .map_err(|e| syn::Error::new(lit_str.span(), e))?;
+
+                    let selector = u32::from_be_bytes(
+                        Keccak256::digest(lit_str.value().as_ref())[..4]
+                            .try_into()
+                            .unwrap(),
+                    );
+
+                    (selector, lit_str.value())
+                }
+                Lit::Int(lit_int) => (lit_int.base10_parse::<u32>()?, String::new()),
+                lit => {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        "Expected literal string or integer",
+                    ))
+                }
+            });
+        }
+    }
+
+    Ok(aliases)
+}
+
+/// Dispatch a precompile call to the handler of its function, reading the selector from the
+/// input of the handle and checking the function modifier before calling the handler.
+/// The modifiers are the ones of the selector enum (see `generate_function_selector`), thus
+/// the arms can't be annotated with `#[view]` or `#[payable]`. The generated `match` is
+/// exhaustive, thus every function of the selector enum must have a handler.
+///
+/// Usage:
+///
+/// ```ignore
+/// #[generate_function_selector]
+/// enum Action {
+///     #[view]
+///     BalanceOf = "balanceOf(address)",
+///     Transfer = "transfer(address,uint256)",
+///     #[payable]
+///     Deposit = "deposit()",
+/// }
+///
+/// fn execute(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+///     dispatch!(handle, Action {
+///         BalanceOf => Self::balance_of,
+///         Transfer => Self::transfer,
+///         Deposit => Self::deposit,
+///     })
+/// }
+/// ```
+///
+/// Handlers are called with the handle and must return the result of the precompile.
+#[proc_macro]
+pub fn dispatch(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as dispatch::Dispatch);
+
+    dispatch::expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derive `EvmEvent` for a struct with named fields, representing a Solidity event with the
+/// same name. Fields annotated with `#[indexed]` are emitted as topics, in declaration order,
+/// while other fields are ABI encoded in the data of the log.
+///
+/// Usage:
+///
+/// ```ignore
+/// #[derive(EvmEvent)]
+/// struct Transfer {
+///     #[indexed]
+///     from: Address,
+///     #[indexed]
+///     to: Address,
+///     value: U256,
+/// }
+/// ```
+///
+/// Uses `keccak256("Transfer(address,address,uint256)")` as its first topic.
+#[proc_macro_derive(EvmEvent, attributes(indexed))]
+pub fn derive_evm_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    event::expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derive `EvmData` for a struct with named fields, representing a Solidity struct.
+/// Fields are encoded as an ABI tuple, in declaration order.
+///
+/// Usage:
+///
+/// ```ignore
+/// #[derive(EvmData)]
+/// struct Order {
+///     maker: Address,
+///     amount: U256,
+///     data: Bytes,
+/// }
+/// ```
+#[proc_macro_derive(EvmData)]
+pub fn derive_evm_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    evm_data::expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
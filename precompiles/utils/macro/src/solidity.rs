@@ -0,0 +1,133 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Mapping of Rust types to their Solidity ABI type names.
+
+use syn::{spanned::Spanned, Expr, ExprLit, GenericArgument, Lit, PathArguments, Type};
+
+/// Solidity ABI name of a Rust type supported by `EvmData`.
+pub fn solidity_type(ty: &Type) -> syn::Result<String> {
+    match ty {
+        Type::Path(type_path) => {
+            let segment = type_path
+                .path
+                .segments
+                .last()
+                .ok_or_else(|| syn::Error::new(ty.span(), "expected a type"))?;
+
+            let name = segment.ident.to_string();
+            match name.as_str() {
+                "Address" | "NonZeroAddress" | "PayableAddress" | "StrictAddress" | "H160" => {
+                    Ok("address".into())
+                }
+                "H256" => Ok("bytes32".into()),
+                "Function" => Ok("function".into()),
+                "U256" | "Amount" | "Scaled" => Ok("uint256".into()),
+                "I256" | "BalanceDelta" => Ok("int256".into()),
+                "bool" | "StrictBool" => Ok("bool".into()),
+                "u8" | "u16" | "u32" | "u64" | "u128" => Ok(format!("uint{}", &name[1..])),
+                "i8" | "i16" | "i32" | "i64" | "i128" => Ok(format!("int{}", &name[1..])),
+                "Bytes" | "BoundedBytes" => Ok("bytes".into()),
+                "String" => Ok("string".into()),
+                "FixedBytes" => Ok(format!("bytes{}", const_generic(segment, ty, 0)?)),
+                "UFixed" => Ok(format!(
+                    "ufixed{}x{}",
+                    const_generic(segment, ty, 0)?,
+                    const_generic(segment, ty, 1)?
+                )),
+                "Vec" => Ok(format!(
+                    "{}[]",
+                    solidity_type(&single_generic(segment, ty)?)?
+                )),
+                "Option" => Ok(format!(
+                    "(bool,{})",
+                    solidity_type(&single_generic(segment, ty)?)?
+                )),
+                _ => Err(syn::Error::new(
+                    ty.span(),
+                    format!("type {} doesn't have a known Solidity equivalent", name),
+                )),
+            }
+        }
+        Type::Array(array) => match &array.len {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }) => Ok(format!(
+                "{}[{}]",
+                solidity_type(&array.elem)?,
+                len.base10_digits()
+            )),
+            len => Err(syn::Error::new(
+                len.span(),
+                "array length must be an integer literal",
+            )),
+        },
+        Type::Tuple(tuple) => Ok(format!(
+            "({})",
+            tuple
+                .elems
+                .iter()
+                .map(solidity_type)
+                .collect::<syn::Result<Vec<_>>>()?
+                .join(",")
+        )),
+        _ => Err(syn::Error::new(
+            ty.span(),
+            "type doesn't have a known Solidity equivalent",
+        )),
+    }
+}
+
+/// Is the Solidity type a value type, which is encoded in a single 32 bytes word.
+pub fn is_value_type(solidity_type: &str) -> bool {
+    !(solidity_type == "bytes"
+        || solidity_type == "string"
+        || solidity_type.ends_with(']')
+        || solidity_type.starts_with('('))
+}
+
+fn single_generic(segment: &syn::PathSegment, ty: &Type) -> syn::Result<Type> {
+    if let PathArguments::AngleBracketed(args) = &segment.arguments {
+        if let Some(GenericArgument::Type(inner)) = args.args.first() {
+            return Ok(inner.clone());
+        }
+    }
+
+    Err(syn::Error::new(
+        ty.span(),
+        "expected a generic type argument",
+    ))
+}
+
+fn const_generic(segment: &syn::PathSegment, ty: &Type, index: usize) -> syn::Result<String> {
+    if let PathArguments::AngleBracketed(args) = &segment.arguments {
+        if let Some(GenericArgument::Const(Expr::Lit(ExprLit {
+            lit: Lit::Int(value),
+            ..
+        }))) = args.args.iter().nth(index)
+        {
+            return Ok(value.base10_digits().into());
+        }
+    }
+
+    Err(syn::Error::new(
+        ty.span(),
+        "expected an integer literal generic argument",
+    ))
+}
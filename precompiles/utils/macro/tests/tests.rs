@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Utils.  If not, see <http://www.gnu.org/licenses/>.
 
+use precompile_utils::FunctionModifier;
 use sha3::{Digest, Keccak256};
 
 #[precompile_utils_macro::generate_function_selector]
@@ -53,3 +54,183 @@ fn test_generate_function_selector() {
     );
     assert_ne!(Action::Toto as u32, Action::Tata as u32);
 }
+
+#[test]
+fn test_function_selector() {
+    assert_eq!(
+        precompile_utils_macro::function_selector!("transfer(address,uint256)"),
+        0xa9059cbb
+    );
+    assert_eq!(
+        precompile_utils_macro::function_selector!("toto()"),
+        Action::Toto as u32
+    );
+    assert_eq!(
+        &precompile_utils_macro::function_selector!("submit((uint256,bytes)[])").to_be_bytes()[..],
+        &Keccak256::digest(b"submit((uint256,bytes)[])")[0..4],
+    );
+}
+
+#[precompile_utils_macro::generate_function_selector]
+pub enum TokenAction {
+    #[view]
+    BalanceOf = "balanceOf(address)",
+    Transfer = "transfer(address,uint256)",
+    #[payable]
+    Deposit = "deposit()",
+    Submit = "submit((address,uint256)[],bytes)",
+}
+
+#[test]
+fn test_generate_function_selector_abi() {
+    assert_eq!(
+        TokenAction::ABI,
+        concat!(
+            "[",
+            r#"{"type":"function","name":"balanceOf","inputs":[{"name":"","type":"address"}],"#,
+            r#""outputs":[],"stateMutability":"view"},"#,
+            r#"{"type":"function","name":"transfer","inputs":[{"name":"","type":"address"},"#,
+            r#"{"name":"","type":"uint256"}],"outputs":[],"stateMutability":"nonpayable"},"#,
+            r#"{"type":"function","name":"deposit","inputs":[],"outputs":[],"#,
+            r#""stateMutability":"payable"},"#,
+            r#"{"type":"function","name":"submit","inputs":[{"name":"","type":"tuple[]","#,
+            r#""components":[{"name":"","type":"address"},{"name":"","type":"uint256"}]},"#,
+            r#"{"name":"","type":"bytes"}],"outputs":[],"stateMutability":"nonpayable"}"#,
+            "]"
+        )
+    );
+    assert_eq!(
+        TokenAction::Transfer as u32,
+        precompile_utils_macro::function_selector!("transfer(address,uint256)")
+    );
+}
+
+#[precompile_utils_macro::generate_function_selector]
+pub enum ViewAction {
+    #[view]
+    #[returns("uint256,(address,bool)[]")]
+    Balances = "balances(address)",
+}
+
+#[test]
+fn test_generate_function_selector_returns() {
+    assert_eq!(
+        ViewAction::ABI,
+        concat!(
+            "[",
+            r#"{"type":"function","name":"balances","inputs":[{"name":"","type":"address"}],"#,
+            r#""outputs":[{"name":"","type":"uint256"},{"name":"","type":"tuple[]","#,
+            r#""components":[{"name":"","type":"address"},{"name":"","type":"bool"}]}],"#,
+            r#""stateMutability":"view"}"#,
+            "]"
+        )
+    );
+    assert_eq!(ViewAction::Balances.returns(), "uint256,(address,bool)[]");
+}
+
+#[precompile_utils_macro::generate_function_selector]
+pub enum LegacyAction {
+    Transfer = "transfer(address,uint256)",
+    Legacy = 0x12345678,
+}
+
+#[test]
+fn test_generate_function_selector_explicit() {
+    assert_eq!(LegacyAction::Legacy as u32, 0x12345678);
+    assert_eq!(
+        LegacyAction::Transfer as u32,
+        precompile_utils_macro::function_selector!("transfer(address,uint256)")
+    );
+    assert_eq!(
+        LegacyAction::ABI,
+        concat!(
+            "[",
+            r#"{"type":"function","name":"transfer","inputs":[{"name":"","type":"address"},"#,
+            r#"{"name":"","type":"uint256"}],"outputs":[],"stateMutability":"nonpayable"}"#,
+            "]"
+        )
+    );
+}
+
+#[test]
+fn test_selector_collisions() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
+
+#[precompile_utils_macro::generate_function_selector]
+#[derive(Debug, PartialEq)]
+pub enum RenamedAction {
+    #[alias("oldTransfer(address,uint256)", 0x12345678)]
+    Transfer = "transfer(address,uint256)",
+    Approve = "approve(address,uint256)",
+}
+
+#[test]
+fn test_generate_function_selector_aliases() {
+    use num_enum::TryFromPrimitive;
+
+    for selector in [
+        precompile_utils_macro::function_selector!("transfer(address,uint256)"),
+        precompile_utils_macro::function_selector!("oldTransfer(address,uint256)"),
+        0x12345678,
+    ] {
+        assert_eq!(
+            RenamedAction::try_from_primitive(selector).ok(),
+            Some(RenamedAction::Transfer)
+        );
+    }
+
+    // The canonical selector is kept.
+    assert_eq!(
+        RenamedAction::Transfer as u32,
+        precompile_utils_macro::function_selector!("transfer(address,uint256)")
+    );
+    assert!(!RenamedAction::ABI.contains("oldTransfer"));
+}
+
+#[test]
+fn test_generate_function_selector_table() {
+    assert_eq!(
+        TokenAction::SELECTORS,
+        &[
+            (0x70a08231, "balanceOf(address)", FunctionModifier::View),
+            (
+                0xa9059cbb,
+                "transfer(address,uint256)",
+                FunctionModifier::NonPayable
+            ),
+            (
+                precompile_utils_macro::function_selector!("deposit()"),
+                "deposit()",
+                FunctionModifier::Payable
+            ),
+            (
+                precompile_utils_macro::function_selector!("submit((address,uint256)[],bytes)"),
+                "submit((address,uint256)[],bytes)",
+                FunctionModifier::NonPayable
+            ),
+        ]
+    );
+
+    assert_eq!(
+        RenamedAction::SELECTORS,
+        &[
+            (
+                0xa9059cbb,
+                "transfer(address,uint256)",
+                FunctionModifier::NonPayable
+            ),
+            (
+                precompile_utils_macro::function_selector!("oldTransfer(address,uint256)"),
+                "oldTransfer(address,uint256)",
+                FunctionModifier::NonPayable
+            ),
+            (0x12345678, "", FunctionModifier::NonPayable),
+            (
+                precompile_utils_macro::function_selector!("approve(address,uint256)"),
+                "approve(address,uint256)",
+                FunctionModifier::NonPayable
+            ),
+        ]
+    );
+}
@@ -0,0 +1,140 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Precompile executing a batch of calls on behalf of its caller.
+
+use crate::{
+    generate_function_selector, out_of_gas, revert_with_reason, subcall, succeed, Address, Bytes,
+    EvmEvent, EvmResult, FunctionModifier, PrecompileHandleExt,
+};
+
+use alloc::vec::Vec;
+use fp_evm::{Precompile, PrecompileFailure, PrecompileHandle, PrecompileOutput};
+use sp_core::U256;
+
+#[generate_function_selector]
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    BatchSome = "batchSome(address[],uint256[],bytes[],uint64[])",
+    BatchSomeUntilFailure = "batchSomeUntilFailure(address[],uint256[],bytes[],uint64[])",
+    BatchAll = "batchAll(address[],uint256[],bytes[],uint64[])",
+}
+
+/// How a batch handles failing subcalls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Execute all subcalls, emitting `SubcallFailed` for the failing ones.
+    BatchSome,
+    /// Stop at the first failing subcall, without reverting the previous ones.
+    BatchSomeUntilFailure,
+    /// Revert the whole batch if any subcall fails.
+    BatchAll,
+}
+
+/// Emitted when the subcall at `index` succeeds.
+#[derive(EvmEvent)]
+pub struct SubcallSucceeded {
+    pub index: U256,
+}
+
+/// Emitted when the subcall at `index` fails.
+#[derive(EvmEvent)]
+pub struct SubcallFailed {
+    pub index: U256,
+}
+
+/// Precompile executing a batch of calls on behalf of its caller.
+/// Each call is described by the items at the same index of the `to`, `value`, `callData`
+/// and `gasLimit` arrays, a gas limit of 0 forwarding all the remaining gas.
+pub struct BatchPrecompile;
+
+impl Precompile for BatchPrecompile {
+    fn execute(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        let mode = match handle.read_selector()? {
+            Action::BatchSome => Mode::BatchSome,
+            Action::BatchSomeUntilFailure => Mode::BatchSomeUntilFailure,
+            Action::BatchAll => Mode::BatchAll,
+        };
+
+        handle.check_function_modifier(FunctionModifier::NonPayable)?;
+
+        Self::batch(handle, mode)
+    }
+}
+
+impl BatchPrecompile {
+    /// Execute the batch of calls of the input with provided mode.
+    /// Fails if called with DELEGATECALL, as the subcalls would then be done on behalf of the
+    /// caller of the delegating contract.
+    pub fn batch(handle: &mut impl PrecompileHandle, mode: Mode) -> EvmResult<PrecompileOutput> {
+        if handle.code_address() != handle.context().address {
+            return Err(revert_with_reason("cannot be called with DELEGATECALL"));
+        }
+
+        let mut input = handle.read_input()?;
+        let to: Vec<Address> = input.read_field("to")?;
+        let value: Vec<U256> = input.read_field("value")?;
+        let call_data: Vec<Bytes> = input.read_field("callData")?;
+        let gas_limit: Vec<u64> = input.read_field("gasLimit")?;
+
+        if value.len() != to.len() || call_data.len() != to.len() || gas_limit.len() != to.len() {
+            return Err(revert_with_reason("arrays must have the same length"));
+        }
+
+        let caller = handle.context().caller;
+
+        let subcalls = to.into_iter().zip(value).zip(call_data).zip(gas_limit);
+        for (index, (((to, value), call_data), gas_limit)) in subcalls.enumerate() {
+            let index = U256::from(index as u64);
+
+            // The cost of the event is recorded first so it can always be emitted.
+            handle.record_log_costs_manual(1, 32)?;
+
+            let gas_limit = match gas_limit {
+                0 => None,
+                gas_limit => Some(gas_limit),
+            };
+
+            let result = match gas_limit {
+                Some(gas_limit) if gas_limit > handle.remaining_gas() => Err(out_of_gas()),
+                _ => subcall::call_from(
+                    handle,
+                    caller,
+                    to.into(),
+                    call_data.into(),
+                    value,
+                    gas_limit,
+                ),
+            };
+
+            match (result, mode) {
+                (Ok(_), _) => SubcallSucceeded { index }.record(handle)?,
+                (Err(failure @ PrecompileFailure::Fatal { .. }), _)
+                | (Err(failure), Mode::BatchAll) => return Err(failure),
+                (Err(_), Mode::BatchSome) => SubcallFailed { index }.record(handle)?,
+                (Err(_), Mode::BatchSomeUntilFailure) => {
+                    SubcallFailed { index }.record(handle)?;
+                    break;
+                }
+            }
+        }
+
+        Ok(succeed([]))
+    }
+}
@@ -17,18 +17,26 @@
 // You should have received a copy of the GNU General Public License
 // along with Utils.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{revert, EvmResult};
-
-use alloc::borrow::ToOwned;
-use core::{any::type_name, ops::Range};
+use crate::{EvmResult, RevertReason};
+
+use alloc::{borrow::ToOwned, format, string::String};
+use core::{
+    any::type_name,
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, Range},
+};
+use frame_support::traits::Get;
 use impl_trait_for_tuples::impl_for_tuples;
-use sp_core::{H160, H256, U256};
+use sp_core::{H160, H256, U256, U512};
 use sp_std::{convert::TryInto, vec, vec::Vec};
 
 /// The `address` type of Solidity.
 /// H160 could represent 2 types of data (bytes20 and address) that are not encoded the same way.
-/// To avoid issues writing H160 is thus not supported.
+/// H160 is encoded as an `address` like this type, which should still be preferred as it
+/// carries the intent. Use `FixedBytes<20>` for `bytes20`.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(transparent)]
 pub struct Address(pub H160);
 
 impl From<H160> for Address {
@@ -43,10 +51,137 @@ impl From<Address> for H160 {
     }
 }
 
+impl Address {
+    /// Convert decoded addresses into `H160`s, reusing the allocation of the vector.
+    pub fn into_h160_vec(addresses: Vec<Address>) -> Vec<H160> {
+        let mut addresses = core::mem::ManuallyDrop::new(addresses);
+        let (ptr, len, capacity) = (
+            addresses.as_mut_ptr(),
+            addresses.len(),
+            addresses.capacity(),
+        );
+
+        // SAFETY: `Address` is a `repr(transparent)` wrapper of `H160`, thus they have the same
+        // size and alignment, and the buffer is not dropped by the original vector.
+        unsafe { Vec::from_raw_parts(ptr as *mut H160, len, capacity) }
+    }
+
+    /// Convert `H160`s into addresses to be encoded, reusing the allocation of the vector.
+    pub fn from_h160_vec(addresses: Vec<H160>) -> Vec<Address> {
+        let mut addresses = core::mem::ManuallyDrop::new(addresses);
+        let (ptr, len, capacity) = (
+            addresses.as_mut_ptr(),
+            addresses.len(),
+            addresses.capacity(),
+        );
+
+        // SAFETY: see `into_h160_vec`.
+        unsafe { Vec::from_raw_parts(ptr as *mut Address, len, capacity) }
+    }
+}
+
+/// The `address` type of Solidity, rejecting words whose upper 12 bytes are not zero when
+/// decoded. `Address` accepts such words for compatibility, ignoring the upper bytes.
+/// It is encoded the same way as `Address`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StrictAddress(pub H160);
+
+impl From<StrictAddress> for H160 {
+    fn from(a: StrictAddress) -> H160 {
+        a.0
+    }
+}
+
+impl From<StrictAddress> for Address {
+    fn from(a: StrictAddress) -> Address {
+        Address(a.0)
+    }
+}
+
+/// The `address` type of Solidity, rejecting the zero address when decoded.
+/// Unlike `Address`, a word whose upper 12 bytes are not zero is rejected as malformed.
+/// It is encoded the same way as `Address`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NonZeroAddress(pub H160);
+
+impl From<NonZeroAddress> for H160 {
+    fn from(a: NonZeroAddress) -> H160 {
+        a.0
+    }
+}
+
+impl From<NonZeroAddress> for Address {
+    fn from(a: NonZeroAddress) -> Address {
+        Address(a.0)
+    }
+}
+
+/// The `address payable` type of Solidity, for addresses intended to receive funds.
+/// It is purely semantic and is encoded the same way as `Address`.
+///
+/// ```
+/// use fp_evm::PrecompileHandle;
+/// use precompile_utils::{subcall, EvmResult, PayableAddress};
+/// use sp_core::U256;
+///
+/// fn pay(
+///     handle: &mut impl PrecompileHandle,
+///     recipient: PayableAddress,
+///     amount: U256,
+/// ) -> EvmResult {
+///     subcall::call(handle, recipient.into(), vec![], amount, None)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PayableAddress(pub H160);
+
+impl From<H160> for PayableAddress {
+    fn from(a: H160) -> PayableAddress {
+        PayableAddress(a)
+    }
+}
+
+impl From<PayableAddress> for H160 {
+    fn from(a: PayableAddress) -> H160 {
+        a.0
+    }
+}
+
+impl From<Address> for PayableAddress {
+    fn from(a: Address) -> PayableAddress {
+        PayableAddress(a.0)
+    }
+}
+
+impl From<PayableAddress> for Address {
+    fn from(a: PayableAddress) -> Address {
+        Address(a.0)
+    }
+}
+
+/// The `bool` type of Solidity, rejecting words other than 0 or 1 when decoded.
+/// `bool` accepts any non-zero word as `true` for compatibility.
+/// It is encoded the same way as `bool`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StrictBool(pub bool);
+
+impl From<bool> for StrictBool {
+    fn from(b: bool) -> StrictBool {
+        StrictBool(b)
+    }
+}
+
+impl From<StrictBool> for bool {
+    fn from(b: StrictBool) -> bool {
+        b.0
+    }
+}
+
 /// The `bytes`/`string` type of Solidity.
 /// It is different from `Vec<u8>` which will be serialized with padding for each `u8` element
 /// of the array, while `Bytes` is tightly packed.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Bytes(pub Vec<u8>);
 
 impl Bytes {
@@ -80,47 +215,506 @@ impl From<Bytes> for Vec<u8> {
     }
 }
 
+/// The `bytesN` types of Solidity, which are right-padded.
+/// Using a size `N` which is not between 1 and 32 fails to compile.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FixedBytes<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> FixedBytes<N> {
+    const VALID_SIZE: () = assert!(
+        N >= 1 && N <= 32,
+        "bytesN must have a size between 1 and 32"
+    );
+}
+
+impl<const N: usize> From<[u8; N]> for FixedBytes<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> From<FixedBytes<N>> for [u8; N] {
+    fn from(bytes: FixedBytes<N>) -> Self {
+        bytes.0
+    }
+}
+
+/// Amount of a token or of the native currency, in its smallest unit (such as wei).
+/// Encoded the same way as `U256`, it avoids confusing amounts with other integers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Amount(pub U256);
+
+impl Amount {
+    /// Amount of `wei` smallest units.
+    pub fn from_wei(wei: impl Into<U256>) -> Self {
+        Self(wei.into())
+    }
+
+    /// Amount of `tokens` whole tokens, for a token with `decimals` decimals.
+    /// Reverts if the amount doesn't fit in 256 bits.
+    pub fn from_tokens(tokens: impl Into<U256>, decimals: u8) -> EvmResult<Self> {
+        U256::from(10u8)
+            .checked_pow(decimals.into())
+            .and_then(|unit| tokens.into().checked_mul(unit))
+            .map(Self)
+            .ok_or_else(|| RevertReason::custom("amount overflow").into())
+    }
+
+    /// Amount in smallest units.
+    pub fn wei(&self) -> U256 {
+        self.0
+    }
+
+    /// Add amounts, reverting on overflow.
+    pub fn checked_add(self, other: Self) -> EvmResult<Self> {
+        self.0
+            .checked_add(other.0)
+            .map(Self)
+            .ok_or_else(|| RevertReason::custom("amount overflow").into())
+    }
+
+    /// Subtract amounts, reverting on underflow.
+    pub fn checked_sub(self, other: Self) -> EvmResult<Self> {
+        self.0
+            .checked_sub(other.0)
+            .map(Self)
+            .ok_or_else(|| RevertReason::custom("amount underflow").into())
+    }
+}
+
+impl From<U256> for Amount {
+    fn from(value: U256) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Amount> for U256 {
+    fn from(amount: Amount) -> Self {
+        amount.0
+    }
+}
+
+/// The external `function` type of Solidity, such as a callback.
+/// Encoded as the address of the contract followed by the selector of the function, right
+/// padded to 32 bytes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Function {
+    pub address: H160,
+    pub selector: [u8; 4],
+}
+
+impl Function {
+    /// Function with provided selector of the contract at `address`.
+    pub fn new(address: impl Into<H160>, selector: u32) -> Self {
+        Self {
+            address: address.into(),
+            selector: selector.to_be_bytes(),
+        }
+    }
+}
+
+/// Unsigned fixed-point number with `DECIMALS` decimals, such as a `1e18`-scaled fraction
+/// (`Scaled<18>`) or basis points (`Scaled<4>`).
+/// Encoded the same way as `U256`, the scale is only carried by the type.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Scaled<const DECIMALS: u32>(pub U256);
+
+impl<const DECIMALS: u32> Scaled<DECIMALS> {
+    /// Number with the raw (already scaled) `value`.
+    pub fn from_raw(value: impl Into<U256>) -> Self {
+        Self(value.into())
+    }
+
+    /// Raw (scaled) value.
+    pub fn raw(&self) -> U256 {
+        self.0
+    }
+
+    /// Scaling factor of the type, which is the raw value of `1`.
+    /// Reverts if it doesn't fit in 256 bits.
+    pub fn unit() -> EvmResult<U256> {
+        U256::from(10u8)
+            .checked_pow(DECIMALS.into())
+            .ok_or_else(|| RevertReason::custom("scale overflow").into())
+    }
+
+    /// Compute `self * mul / div`, which keeps the scale of the result.
+    /// The intermediate product is computed in 512 bits and the result is rounded down.
+    /// Reverts on division by zero or if the result doesn't fit in 256 bits.
+    pub fn mul_div(self, mul: Self, div: Self) -> EvmResult<Self> {
+        if div.0.is_zero() {
+            return Err(RevertReason::custom("division by zero").into());
+        }
+
+        (self.0.full_mul(mul.0) / U512::from(div.0))
+            .try_into()
+            .map(Self)
+            .map_err(|_| RevertReason::custom("scaled value overflow").into())
+    }
+
+    /// Multiply scaled numbers, keeping the scale of the result (rounded down).
+    pub fn checked_mul(self, other: Self) -> EvmResult<Self> {
+        self.mul_div(other, Self(Self::unit()?))
+    }
+
+    /// Divide scaled numbers, keeping the scale of the result (rounded down).
+    pub fn checked_div(self, other: Self) -> EvmResult<Self> {
+        self.mul_div(Self(Self::unit()?), other)
+    }
+}
+
+impl<const DECIMALS: u32> From<U256> for Scaled<DECIMALS> {
+    fn from(value: U256) -> Self {
+        Self(value)
+    }
+}
+
+impl<const DECIMALS: u32> From<Scaled<DECIMALS>> for U256 {
+    fn from(value: Scaled<DECIMALS>) -> Self {
+        value.0
+    }
+}
+
+/// The `ufixedMxN` type of Solidity, an unsigned fixed-point number of `M` bits with `N`
+/// decimals. Rarely used by contracts, it is supported for decoding data emitted by tooling.
+/// Encoded as its `M` bits mantissa, which is the value multiplied by `10^N`.
+/// Using a size `M` which is not a multiple of 8 between 8 and 256, or more than 80 decimals,
+/// fails to compile.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub struct UFixed<const M: usize, const N: usize>(U256);
+
+impl<const M: usize, const N: usize> UFixed<M, N> {
+    const VALID_SIZE: () = assert!(
+        M >= 8 && M <= 256 && M % 8 == 0 && N <= 80,
+        "ufixedMxN must have a size M multiple of 8 between 8 and 256 and at most 80 decimals N"
+    );
+
+    /// Number with provided `mantissa`, which is the value multiplied by `10^N`.
+    /// Reverts if the mantissa doesn't fit in `M` bits.
+    pub fn from_mantissa(mantissa: impl Into<U256>) -> EvmResult<Self> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SIZE;
+
+        let mantissa = mantissa.into();
+        if mantissa.bits() > M {
+            return Err(
+                RevertReason::custom(format!("value too large for ufixed{}x{}", M, N)).into(),
+            );
+        }
+
+        Ok(Self(mantissa))
+    }
+
+    /// Mantissa of the number, which is the value multiplied by `10^N`.
+    pub fn mantissa(&self) -> U256 {
+        self.0
+    }
+
+    /// Scaling factor of the type, which is the mantissa of `1`.
+    /// Reverts if it doesn't fit in 256 bits, which is the case for more than 77 decimals.
+    pub fn unit() -> EvmResult<U256> {
+        Self::wide_unit()
+            .try_into()
+            .map_err(|_| RevertReason::custom("scale overflow").into())
+    }
+
+    fn wide_unit() -> U512 {
+        // Doesn't overflow as `N` is at most 80.
+        U512::from(10u8).pow(U512::from(N))
+    }
+
+    /// Add numbers, reverting if the result doesn't fit in `M` bits.
+    pub fn checked_add(self, other: Self) -> EvmResult<Self> {
+        let mantissa = self
+            .0
+            .checked_add(other.0)
+            .ok_or_else(|| RevertReason::custom("fixed value overflow"))?;
+
+        Self::from_mantissa(mantissa)
+    }
+
+    /// Subtract numbers, reverting on underflow.
+    pub fn checked_sub(self, other: Self) -> EvmResult<Self> {
+        self.0
+            .checked_sub(other.0)
+            .map(Self)
+            .ok_or_else(|| RevertReason::custom("fixed value underflow").into())
+    }
+
+    /// Multiply numbers, keeping the scale of the result (rounded down).
+    /// Reverts if the result doesn't fit in `M` bits.
+    pub fn checked_mul(self, other: Self) -> EvmResult<Self> {
+        Self::mul_div(self.0.into(), other.0.into(), Self::wide_unit())
+    }
+
+    /// Divide numbers, keeping the scale of the result (rounded down).
+    /// Reverts on division by zero or if the result doesn't fit in `M` bits.
+    pub fn checked_div(self, other: Self) -> EvmResult<Self> {
+        Self::mul_div(self.0.into(), Self::wide_unit(), other.0.into())
+    }
+
+    /// Compute `a * b / div` in 512 bits, rounded down.
+    fn mul_div(a: U512, b: U512, div: U512) -> EvmResult<Self> {
+        if div.is_zero() {
+            return Err(RevertReason::custom("division by zero").into());
+        }
+
+        let mantissa: U256 = (a
+            .checked_mul(b)
+            .ok_or_else(|| RevertReason::custom("fixed value overflow"))?
+            / div)
+            .try_into()
+            .map_err(|_| RevertReason::custom("fixed value overflow"))?;
+
+        Self::from_mantissa(mantissa)
+    }
+}
+
+/// The `int256` type of Solidity.
+/// The value is stored in two's complement, which is how the EVM represents signed integers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct I256(pub U256);
+
+impl I256 {
+    /// Smallest value of `int256` (`-2^255`).
+    pub const MIN: Self = Self(U256([0, 0, 0, 0x8000_0000_0000_0000]));
+    /// Largest value of `int256` (`2^255 - 1`).
+    pub const MAX: Self = Self(U256([u64::MAX, u64::MAX, u64::MAX, 0x7fff_ffff_ffff_ffff]));
+
+    /// Zero value.
+    pub fn zero() -> Self {
+        Self(U256::zero())
+    }
+
+    /// Returns true if the value is strictly negative.
+    pub fn is_negative(&self) -> bool {
+        self.0.bit(255)
+    }
+
+    /// Absolute value as an unsigned integer.
+    /// Doesn't overflow since `|int256::MIN|` fits in `uint256`.
+    pub fn unsigned_abs(&self) -> U256 {
+        if self.is_negative() {
+            (!self.0).overflowing_add(U256::one()).0
+        } else {
+            self.0
+        }
+    }
+}
+
+/// Signed change of a balance, such as an adjustment which can either credit or debit an
+/// account. Encoded the same way as `I256`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BalanceDelta(pub I256);
+
+impl BalanceDelta {
+    /// Apply the delta to `balance`, adding it if positive and subtracting it if negative.
+    /// Reverts with "insufficient balance" if subtracting more than the balance, and with
+    /// "balance overflow" if the result doesn't fit in 256 bits.
+    pub fn apply_to(&self, balance: U256) -> EvmResult<U256> {
+        let amount = self.0.unsigned_abs();
+
+        if self.0.is_negative() {
+            balance
+                .checked_sub(amount)
+                .ok_or_else(|| RevertReason::custom("insufficient balance").into())
+        } else {
+            balance
+                .checked_add(amount)
+                .ok_or_else(|| RevertReason::custom("balance overflow").into())
+        }
+    }
+}
+
+impl From<I256> for BalanceDelta {
+    fn from(value: I256) -> Self {
+        Self(value)
+    }
+}
+
+impl From<BalanceDelta> for I256 {
+    fn from(delta: BalanceDelta) -> Self {
+        delta.0
+    }
+}
+
+/// The `bytes` type of Solidity with a maximum length of `S::get()`.
+/// The length is checked when reading before the data is copied, which allows to accept
+/// variable-length bytes while bounding memory usage.
+pub struct BoundedBytes<S> {
+    inner: Vec<u8>,
+    _phantom: PhantomData<S>,
+}
+
+impl<S> Clone for BoundedBytes<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> fmt::Debug for BoundedBytes<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BoundedBytes").field(&self.inner).finish()
+    }
+}
+
+impl<S> PartialEq for BoundedBytes<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<S> Eq for BoundedBytes<S> {}
+
+impl<S> Deref for BoundedBytes<S> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.inner
+    }
+}
+
+impl<S: Get<u32>> TryFrom<Vec<u8>> for BoundedBytes<S> {
+    type Error = RevertReason;
+
+    fn try_from(inner: Vec<u8>) -> Result<Self, Self::Error> {
+        if inner.len() > S::get() as usize {
+            return Err(RevertReason::custom("bytes too long"));
+        }
+
+        Ok(Self {
+            inner,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<S> From<BoundedBytes<S>> for Vec<u8> {
+    fn from(b: BoundedBytes<S>) -> Vec<u8> {
+        b.inner
+    }
+}
+
+/// Default maximum amount of nested pointers followed by an `EvmDataReader`.
+/// Solidity types are rarely nested more than a few levels deep, this leaves a lot of room for
+/// legitimate data while bounding the recursion on untrusted input.
+pub const DEFAULT_MAX_POINTER_DEPTH: usize = 64;
+
 /// Wrapper around an EVM input slice, helping to parse it.
 /// Provide functions to parse common types.
 #[derive(Clone, Copy, Debug)]
 pub struct EvmDataReader<'a> {
     input: &'a [u8],
     cursor: usize,
+    // Offset of `input` in the input of the root reader, used to report the position of
+    // failed reads in nested readers (pointed data, array items).
+    offset: usize,
+    // End of the furthest read in the input of the root reader, including the reads of nested
+    // readers done through `read_pointed`. Allows to check the whole input has been read,
+    // including the pointed data.
+    read_end: usize,
+    // Amount of pointers followed to reach this reader, and the maximum allowed.
+    depth: usize,
+    max_depth: usize,
+    // Amount of pointers that can still be followed, including by nested readers merged back
+    // through `read_pointed`.
+    pointer_budget: usize,
 }
 
 impl<'a> EvmDataReader<'a> {
     /// Create a new input parser.
     pub fn new(input: &'a [u8]) -> Self {
-        Self { input, cursor: 0 }
+        Self {
+            input,
+            cursor: 0,
+            offset: 0,
+            read_end: 0,
+            depth: 0,
+            max_depth: DEFAULT_MAX_POINTER_DEPTH,
+            // Each pointer of a valid encoding takes a 32 bytes word of the input and is
+            // followed once.
+            pointer_budget: input.len() / 32,
+        }
     }
 
-    /// Create a new input parser from a selector-initial input.
-    pub fn read_selector<T>(input: &'a [u8]) -> EvmResult<T>
-    where
-        T: num_enum::TryFromPrimitive<Primitive = u32>,
-    {
+    /// Set the maximum amount of nested pointers that can be followed, which defaults to
+    /// `DEFAULT_MAX_POINTER_DEPTH`. Reading data nested deeper reverts with
+    /// "malformed nested encoding".
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Create a reader targeting `input[start..]`, keeping track of its offset in the root
+    /// reader input.
+    fn sub_reader(&self, start: usize) -> Option<Self> {
+        Some(Self {
+            input: self.input.get(start..)?,
+            cursor: 0,
+            offset: self.offset + start,
+            read_end: self.read_end,
+            depth: self.depth,
+            max_depth: self.max_depth,
+            pointer_budget: self.pointer_budget,
+        })
+    }
+
+    /// Read the raw selector of a selector-initial input.
+    /// The input is left untouched, which allows to dispatch on the selector while still
+    /// having access to the full input.
+    pub fn peek_selector(input: &[u8]) -> EvmResult<u32> {
         if input.len() < 4 {
-            return Err(revert("tried to parse selector out of bounds"));
+            return Err(RevertReason::read_out_of_bounds("selector").into());
         }
 
         let mut buffer = [0u8; 4];
         buffer.copy_from_slice(&input[0..4]);
-        let selector = T::try_from_primitive(u32::from_be_bytes(buffer)).map_err(|_| {
+
+        Ok(u32::from_be_bytes(buffer))
+    }
+
+    /// Create a new input parser from a selector-initial input.
+    pub fn read_selector<T>(input: &'a [u8]) -> EvmResult<T>
+    where
+        T: num_enum::TryFromPrimitive<Primitive = u32>,
+    {
+        let selector = T::try_from_primitive(Self::peek_selector(input)?).map_err(|_| {
             log::trace!(
                 target: "precompile-utils",
                 "Failed to match function selector for {}",
                 type_name::<T>()
             );
-            revert("unknown selector")
+            RevertReason::custom("unknown selector")
         })?;
 
         Ok(selector)
     }
 
+    /// Read the selector of an input like `read_selector`, but follows the Solidity
+    /// convention instead of reverting on empty input or unknown selectors: empty input
+    /// targets `receive` and unknown selectors target `fallback`.
+    /// Reverts if the input is too short to contain a selector but isn't empty.
+    pub fn read_selector_or_fallback<T>(input: &'a [u8]) -> EvmResult<Selector<T>>
+    where
+        T: num_enum::TryFromPrimitive<Primitive = u32>,
+    {
+        if input.is_empty() {
+            return Ok(Selector::Receive);
+        }
+
+        Ok(match T::try_from_primitive(Self::peek_selector(input)?) {
+            Ok(selector) => Selector::Known(selector),
+            Err(_) => Selector::Fallback,
+        })
+    }
+
     /// Create a new input parser from a selector-initial input.
     pub fn new_skip_selector(input: &'a [u8]) -> EvmResult<Self> {
         if input.len() < 4 {
-            return Err(revert("input is too short"));
+            return Err(RevertReason::custom("input is too short").into());
         }
 
         Ok(Self::new(&input[4..]))
@@ -131,68 +725,224 @@ impl<'a> EvmDataReader<'a> {
         if self.input.len() >= self.cursor + args * 32 {
             Ok(())
         } else {
-            Err(revert("input doesn't match expected length"))
+            Err(RevertReason::custom("input doesn't match expected length").into())
         }
     }
 
+    /// Position of the reading cursor in the input of this reader.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Human-readable view of the input, with one 32 bytes word per line and the word the
+    /// cursor is in marked by `<- cursor`. Useful to debug decoding issues.
+    pub fn pretty(&self) -> String {
+        pretty_words(self.input, Some(self.cursor))
+    }
+
+    /// Amount of bytes remaining after the cursor.
+    /// Doesn't account for the data pointed by already read values.
+    pub fn remaining_bytes(&self) -> usize {
+        self.input.len().saturating_sub(self.cursor)
+    }
+
     /// Read data from the input.
     pub fn read<T: EvmData>(&mut self) -> EvmResult<T> {
         T::read(self)
     }
 
-    /// Read raw bytes from the input.
-    /// Doesn't handle any alignment checks, prefer using `read` instead of possible.
-    /// Returns an error if trying to parse out of bounds.
-    pub fn read_raw_bytes(&mut self, len: usize) -> EvmResult<&[u8]> {
-        let range = self.move_cursor(len)?;
+    /// Read data from the input, naming the field being read.
+    /// In case of failure the revert reason is prefixed by the field name.
+    pub fn read_field<T: EvmData>(&mut self, field: &'static str) -> EvmResult<T> {
+        T::read(self).map_err(|e| crate::revert::in_field(e, field))
+    }
 
-        let data = self
-            .input
-            .get(range)
-            .ok_or_else(|| revert("tried to parse raw bytes out of bounds"))?;
+    /// Skip `words` 32 bytes words without decoding them.
+    /// Reverts if it would skip past the end of the input.
+    pub fn skip(&mut self, words: usize) -> EvmResult {
+        let len = words
+            .checked_mul(32)
+            .ok_or_else(|| RevertReason::custom("data reading cursor overflow"))?;
 
-        Ok(data)
+        self.read_slice(len, "skipped words")?;
+
+        Ok(())
     }
 
-    /// Reads a pointer, returning a reader targetting the pointed location.
-    pub fn read_pointer(&mut self) -> EvmResult<Self> {
-        let offset: usize = self
-            .read::<U256>()
-            .map_err(|_| revert("tried to parse array offset out of bounds"))?
-            .try_into()
-            .map_err(|_| revert("array offset is too large"))?;
+    /// Read a dynamic array with at most `max` items.
+    /// The length is checked before allocating the array, which protects against inputs
+    /// claiming a huge length.
+    pub fn read_array_capped<T: EvmData>(&mut self, max: usize) -> EvmResult<Vec<T>> {
+        read_array(self, Some(max))
+    }
 
-        if offset >= self.input.len() {
-            return Err(revert("pointer points out of bounds"));
+    /// Read parallel `address[]` and `uint256[]` arrays with at most `max` items each, such as
+    /// the recipients and amounts of a batch transfer, paired by index.
+    /// Reverts with "mismatched array lengths" if the arrays don't have the same length.
+    pub fn read_address_amount_pairs(&mut self, max: usize) -> EvmResult<Vec<(H160, U256)>> {
+        let addresses: Vec<Address> = self.read_array_capped(max)?;
+        let amounts: Vec<U256> = self.read_array_capped(max)?;
+
+        if addresses.len() != amounts.len() {
+            return Err(RevertReason::custom("mismatched array lengths").into());
         }
 
-        Ok(Self {
-            input: &self.input[offset..],
-            cursor: 0,
-        })
+        Ok(addresses
+            .into_iter()
+            .map(|address| address.0)
+            .zip(amounts)
+            .collect())
     }
 
-    /// Read remaining bytes
-    pub fn read_till_end(&mut self) -> EvmResult<&[u8]> {
-        let range = self.move_cursor(self.input.len() - self.cursor)?;
-
-        let data = self
-            .input
-            .get(range)
-            .ok_or_else(|| revert("tried to parse raw bytes out of bounds"))?;
+    /// Read a Solidity enum, encoded as a `uint8` of the variant index.
+    /// Reverts if the value doesn't match any variant.
+    pub fn read_enum<T>(&mut self) -> EvmResult<T>
+    where
+        T: num_enum::TryFromPrimitive<Primitive = u8>,
+    {
+        let value: u8 = self.read()?;
 
-        Ok(data)
+        T::try_from_primitive(value)
+            .map_err(|_| RevertReason::custom(format!("invalid enum value {}", value)).into())
     }
 
-    /// Move the reading cursor with provided length, and return a range from the previous cursor
-    /// location to the new one.
+    /// Read a `uint256` that must be strictly positive, such as an amount or a price.
+    /// Reverts with "{what} must be nonzero" if it is zero.
+    pub fn read_nonzero_u256(&mut self, what: &'static str) -> EvmResult<U256> {
+        let value: U256 = self.read()?;
+
+        if value.is_zero() {
+            return Err(RevertReason::custom(format!("{} must be nonzero", what)).into());
+        }
+
+        Ok(value)
+    }
+
+    /// Read raw bytes from the input.
+    /// Doesn't handle any alignment checks, prefer using `read` instead of possible.
+    /// Returns an error if trying to parse out of bounds.
+    pub fn read_raw_bytes(&mut self, len: usize) -> EvmResult<&[u8]> {
+        self.read_slice(len, "raw bytes")
+    }
+
+    /// Reads a pointer, returning a reader targetting the pointed location.
+    /// The offset is relative to the start of this reader's input, as ABI offsets are relative
+    /// to the start of their container. Reverts if it points outside of the input.
+    /// Also reverts with "malformed nested encoding" if the maximum depth of nested pointers
+    /// is exceeded, or if more pointers are followed than the input has words (which only
+    /// happens with pointers targeting already read data).
+    pub fn read_pointer(&mut self) -> EvmResult<Self> {
+        let offset: usize = U256::from_big_endian(self.read_slice(32, "pointer")?)
+            .try_into()
+            .map_err(|_| RevertReason::value_is_too_large("pointer"))?;
+
+        if offset >= self.input.len() {
+            return Err(RevertReason::PointerToOutofbound.into());
+        }
+
+        if self.depth >= self.max_depth {
+            return Err(RevertReason::custom("malformed nested encoding").into());
+        }
+
+        self.pointer_budget = self
+            .pointer_budget
+            .checked_sub(1)
+            .ok_or_else(|| RevertReason::custom("malformed nested encoding"))?;
+
+        let mut reader = self
+            .sub_reader(offset)
+            .ok_or_else(|| RevertReason::PointerToOutofbound)?;
+        reader.depth += 1;
+
+        Ok(reader)
+    }
+
+    /// Reads a pointer and reads the pointed data with `read`, see `read_pointer`.
+    /// Unlike reading from the reader returned by `read_pointer`, the reads of `read` are
+    /// accounted by `expect_end`.
+    pub fn read_pointed<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> EvmResult<T>,
+    ) -> EvmResult<T> {
+        let mut reader = self.read_pointer()?;
+        let value = read(&mut reader)?;
+        self.merge_reads(&reader);
+
+        Ok(value)
+    }
+
+    /// Read all the remaining bytes, moving the cursor to the end of the input.
+    /// Returns an empty slice if the cursor is already at the end.
+    pub fn read_till_end(&mut self) -> EvmResult<&[u8]> {
+        // The cursor can be past the end after a failed read.
+        let start = self.cursor.min(self.input.len());
+        self.cursor = self.input.len();
+        self.mark_read(self.input.len());
+
+        Ok(&self.input[start..])
+    }
+
+    /// Check that the whole input has been read, including the data pointed by the read
+    /// values (which is after the cursor of the root reader). Only the pointed data read with
+    /// `read_pointed`, as done by the `EvmData` implementations, is accounted.
+    /// Reverts if there are trailing bytes.
+    pub fn expect_end(&self) -> EvmResult {
+        let read_end = self.read_end.max(self.offset + self.cursor);
+        let input_end = self.offset + self.input.len();
+
+        if read_end < input_end {
+            return Err(RevertReason::custom(format!(
+                "unexpected trailing {} bytes",
+                input_end - read_end
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Mark the input as read up to `end` (relative to the input of this reader).
+    fn mark_read(&mut self, end: usize) {
+        let end = self.offset + end.min(self.input.len());
+        self.read_end = self.read_end.max(end);
+    }
+
+    /// Account the reads of a reader nested in this one, such as one targeting pointed data.
+    fn merge_reads(&mut self, nested: &Self) {
+        self.read_end = self.read_end.max(nested.read_end);
+        self.pointer_budget = nested.pointer_budget;
+    }
+
+    /// Read `len` bytes from the input, reporting the position of the read in case of failure.
+    /// `what` describes the data being read.
+    fn read_slice(&mut self, len: usize, what: &'static str) -> EvmResult<&'a [u8]> {
+        let offset = self.offset + self.cursor;
+        let range = self.move_cursor(len)?;
+
+        let input = self.input;
+        let end = range.end;
+        let data = input
+            .get(range)
+            .ok_or_else(|| RevertReason::ReadBeyondInput {
+                what,
+                offset,
+                input_len: self.offset + input.len(),
+            })?;
+
+        self.mark_read(end);
+
+        Ok(data)
+    }
+
+    /// Move the reading cursor with provided length, and return a range from the previous cursor
+    /// location to the new one.
     /// Checks cursor overflows.
     fn move_cursor(&mut self, len: usize) -> EvmResult<Range<usize>> {
         let start = self.cursor;
         let end = self
             .cursor
             .checked_add(len)
-            .ok_or_else(|| revert("data reading cursor overflow"))?;
+            .ok_or_else(|| RevertReason::custom("data reading cursor overflow"))?;
 
         self.cursor = end;
 
@@ -200,6 +950,17 @@ impl<'a> EvmDataReader<'a> {
     }
 }
 
+/// Function targeted by an input, see `EvmDataReader::read_selector_or_fallback`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Selector<T> {
+    /// Function with a known selector.
+    Known(T),
+    /// Unknown selector, handled by the Solidity `fallback` function.
+    Fallback,
+    /// Empty input, handled by the Solidity `receive` function.
+    Receive,
+}
+
 /// Help build an EVM input/output data.
 ///
 /// Functions takes `self` to allow chaining all calls like
@@ -234,9 +995,22 @@ impl EvmDataWriter {
         }
     }
 
+    /// Creates a new empty output builder (without selector), pre-allocating the space of
+    /// `words` 32 bytes words.
+    /// The data grows as needed like a `Vec`, with amortized reallocations, but pre-allocating
+    /// avoids them when the size of the output is known in advance (such as large arrays).
+    /// Pointed data is kept separately until `build` and doesn't use this capacity.
+    pub fn with_capacity(words: usize) -> Self {
+        Self {
+            data: Vec::with_capacity(words.saturating_mul(32)),
+            offset_data: vec![],
+            selector: None,
+        }
+    }
+
     /// Creates a new empty output builder with provided selector.
-    /// Selector will only be appended before the data when calling
-    /// `build` to not mess with the offsets.
+    /// Selector will only be appended (big-endian) before the data when calling
+    /// `build` to not mess with the offsets, producing calldata ready to be used for a subcall.
     pub fn new_with_selector(selector: impl Into<u32>) -> Self {
         Self {
             data: vec![],
@@ -258,6 +1032,23 @@ impl EvmDataWriter {
         }
     }
 
+    /// Human-readable view of the data that would be built, with the selector (if any) and
+    /// then one 32 bytes word per line. Useful to debug encoding issues.
+    pub fn pretty(&self) -> String {
+        let mut writer = self.clone();
+        let selector = writer.selector.take();
+        let data = writer.build();
+
+        match selector {
+            Some(selector) => format!(
+                "0x{:08x}  // selector\n{}",
+                selector,
+                pretty_words(&data, None)
+            ),
+            None => pretty_words(&data, None),
+        }
+    }
+
     /// Add offseted data at the end of this writer's data, updating the offsets.
     fn bake_offsets(output: &mut Vec<u8>, offsets: Vec<OffsetDatum>) {
         for mut offset_datum in offsets {
@@ -271,135 +1062,581 @@ impl EvmDataWriter {
             // array, which is corrected by `offset_shift`.
             let free_space_offset = output.len() - offset_datum.offset_shift;
 
-            // Override dummy offset to the offset it will be in the final output.
-            U256::from(free_space_offset)
-                .to_big_endian(&mut output[offset_position..offset_position_end]);
+            // Override dummy offset to the offset it will be in the final output.
+            U256::from(free_space_offset)
+                .to_big_endian(&mut output[offset_position..offset_position_end]);
+
+            // Append this data at the end of the current output.
+            output.append(&mut offset_datum.data);
+        }
+    }
+
+    /// Write arbitrary bytes.
+    /// Doesn't handle any alignement checks, prefer using `write` instead if possible.
+    fn write_raw_bytes(mut self, value: &[u8]) -> Self {
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    /// Write data of requested type.
+    pub fn write<T: EvmData>(mut self, value: T) -> Self {
+        T::write(&mut self, value);
+        self
+    }
+
+    /// Writes a pointer to given data.
+    /// The data will be appended when calling `build`.
+    /// Initially write a dummy value as offset in this writer's data, which will be replaced by
+    /// the correct offset once the pointed data is appended.
+    ///
+    /// Takes `&mut self` since its goal is to be used inside `EvmData` impl and not in chains.
+    pub fn write_pointer(&mut self, data: Vec<u8>) {
+        let slot = self.reserve_pointer();
+        self.write_data(slot, data);
+    }
+
+    /// Reserves a pointer whose pointed data is provided later with `write_data`.
+    /// Allows to write the heads of multiple dynamic values before knowing their data.
+    /// Every reserved pointer must be provided its data before calling `build`.
+    pub fn reserve_pointer(&mut self) -> PointerSlot {
+        let offset_position = self.data.len();
+        H256::write(self, H256::repeat_byte(0xff));
+
+        PointerSlot(offset_position)
+    }
+
+    /// Provides the data pointed by a pointer reserved with `reserve_pointer` in this writer.
+    /// The data will be appended when calling `build`, in the order of the `write_data` calls,
+    /// and the reserved pointer will be replaced by its offset.
+    pub fn write_data(&mut self, slot: PointerSlot, data: Vec<u8>) {
+        self.offset_data.push(OffsetDatum {
+            offset_position: slot.0,
+            data,
+            offset_shift: 0,
+        });
+    }
+}
+
+/// Render `data` with one 32 bytes word per line as `0xWORD  // word N`, marking the word
+/// `cursor` is in (or the end of the data).
+fn pretty_words(data: &[u8], cursor: Option<usize>) -> String {
+    let mut lines = vec![];
+
+    for (index, word) in data.chunks(32).enumerate() {
+        let mut line = String::from("0x");
+        for byte in word {
+            line.push_str(&format!("{:02x}", byte));
+        }
+        line.push_str(&format!("  // word {}", index));
+
+        if cursor.map(|cursor| cursor / 32) == Some(index) {
+            line.push_str(" <- cursor");
+        }
+
+        lines.push(line);
+    }
+
+    if matches!(cursor, Some(cursor) if cursor >= data.len()) {
+        lines.push("// end <- cursor".to_owned());
+    }
+
+    lines.join("\n")
+}
+
+/// Pointer reserved in the data of an `EvmDataWriter`, waiting for its pointed data.
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointerSlot(usize);
+
+impl Default for EvmDataWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builder of the output of a function checked against its Solidity return types, such as
+/// `(uint256,bytes)`.
+///
+/// In debug builds, writing a value whose `EvmData::solidity_type` doesn't have the same
+/// encoding as the next declared type, writing too many values or building before all values
+/// are written panics. Values of smaller integer types than declared (such as `u128` for a
+/// `uint256`) and `Bytes` for a `string` are accepted. Types whose `solidity_type` is unknown
+/// are not checked. Release builds behave exactly like an `EvmDataWriter`.
+///
+/// The builder of a function is usually created from the `#[returns(..)]` attribute of its
+/// variant in a selector enum, with the generated `return_builder` function.
+#[derive(Clone, Debug)]
+pub struct ReturnBuilder {
+    writer: EvmDataWriter,
+    #[cfg(debug_assertions)]
+    returns: Vec<String>,
+    #[cfg(debug_assertions)]
+    written: usize,
+}
+
+impl ReturnBuilder {
+    /// Creates a new output builder for provided return types.
+    /// Enclosing parentheses are optional: `(uint256,bool)` and `uint256,bool` are the same.
+    pub fn new(returns: &str) -> Self {
+        #[cfg(not(debug_assertions))]
+        let _ = returns;
+
+        Self {
+            writer: EvmDataWriter::new(),
+            #[cfg(debug_assertions)]
+            returns: split_solidity_types(returns),
+            #[cfg(debug_assertions)]
+            written: 0,
+        }
+    }
+
+    /// Write the next return value.
+    pub fn write<T: EvmData>(mut self, value: T) -> Self {
+        #[cfg(debug_assertions)]
+        {
+            let expected = self.returns.get(self.written).unwrap_or_else(|| {
+                panic!(
+                    "only {} return values are declared but more were written",
+                    self.returns.len()
+                )
+            });
+
+            if let Some(actual) = T::solidity_type() {
+                assert!(
+                    abi_compatible(expected, &actual),
+                    "return value {} should be `{}` but `{}` was written",
+                    self.written,
+                    expected,
+                    actual
+                );
+            }
+
+            self.written += 1;
+        }
+
+        self.writer = self.writer.write(value);
+        self
+    }
+
+    /// Return the built data.
+    pub fn build(self) -> Vec<u8> {
+        #[cfg(debug_assertions)]
+        assert!(
+            self.written == self.returns.len(),
+            "{} return values are declared but {} were written",
+            self.returns.len(),
+            self.written
+        );
+
+        self.writer.build()
+    }
+}
+
+/// Whether a value of Solidity type `written` is encoded like a value of type `declared`,
+/// such as a `uint128` for a `uint256` or `bytes` for a `string`. Tuples and arrays are
+/// compared item by item.
+#[cfg(debug_assertions)]
+fn abi_compatible(declared: &str, written: &str) -> bool {
+    fn int_bits(ty: &str, prefix: &str) -> Option<u16> {
+        match ty.strip_prefix(prefix)? {
+            "" => Some(256),
+            bits => bits.parse().ok(),
+        }
+    }
+
+    fn split_array(ty: &str) -> Option<(&str, &str)> {
+        if !ty.ends_with(']') {
+            return None;
+        }
+
+        ty.rfind('[').map(|start| ty.split_at(start))
+    }
+
+    if declared == written {
+        return true;
+    }
+
+    if let (Some((declared, declared_len)), Some((written, written_len))) =
+        (split_array(declared), split_array(written))
+    {
+        return declared_len == written_len && abi_compatible(declared, written);
+    }
+
+    if declared.starts_with('(') && written.starts_with('(') {
+        let declared = split_solidity_types(declared);
+        let written = split_solidity_types(written);
+
+        return declared.len() == written.len()
+            && declared
+                .iter()
+                .zip(&written)
+                .all(|(declared, written)| abi_compatible(declared, written));
+    }
+
+    match (declared, written) {
+        ("string", "bytes") | ("bytes", "string") => true,
+        _ => match (int_bits(declared, "uint"), int_bits(written, "uint")) {
+            (Some(declared), Some(written)) => written <= declared,
+            _ => match (int_bits(declared, "int"), int_bits(written, "int")) {
+                (Some(declared), Some(written)) => written <= declared,
+                _ => false,
+            },
+        },
+    }
+}
+
+/// Split a list of Solidity types on its top-level commas, ignoring whitespaces and
+/// enclosing parentheses.
+#[cfg(debug_assertions)]
+fn split_solidity_types(types: &str) -> Vec<String> {
+    let types: String = types.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut types = types.as_str();
+
+    // Only strip parentheses enclosing the whole list, not the ones of a leading tuple
+    // followed by other types like in `(uint8,bool),address`.
+    if types.starts_with('(') && types.ends_with(')') {
+        let mut depth = 0usize;
+        let encloses = types.char_indices().all(|(i, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            depth > 0 || i == types.len() - 1
+        });
+
+        if encloses {
+            types = &types[1..types.len() - 1];
+        }
+    }
+
+    let mut split = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in types.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                split.push(types[start..i].to_owned());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    if !types[start..].is_empty() {
+        split.push(types[start..].to_owned());
+    }
+
+    split
+}
+
+/// Data that can be converted from and to EVM data types.
+pub trait EvmData: Sized {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self>;
+    fn write(writer: &mut EvmDataWriter, value: Self);
+    fn has_static_size() -> bool;
+
+    /// Solidity ABI type of the data, such as `uint256` or `(address,bytes)[]`.
+    /// `None` if unknown, which is the default for types implemented outside of this crate.
+    fn solidity_type() -> Option<String> {
+        None
+    }
+}
+
+#[impl_for_tuples(1, 18)]
+impl EvmData for Tuple {
+    fn has_static_size() -> bool {
+        for_tuples!(#( Tuple::has_static_size() )&*)
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some(format!(
+            "({})",
+            for_tuples!([ #( Tuple::solidity_type()? ),* ]).join(",")
+        ))
+    }
+
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        if !Self::has_static_size() {
+            reader.read_pointed(|reader| Ok(for_tuples!( ( #( reader.read::<Tuple>()? ),* ) )))
+        } else {
+            Ok(for_tuples!( ( #( reader.read::<Tuple>()? ),* ) ))
+        }
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        if !Self::has_static_size() {
+            let mut inner_writer = EvmDataWriter::new();
+            for_tuples!( #( Tuple::write(&mut inner_writer, value.Tuple); )* );
+            writer.write_pointer(inner_writer.build());
+        } else {
+            for_tuples!( #( Tuple::write(writer, value.Tuple); )* );
+        }
+    }
+}
+
+impl EvmData for H256 {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        let data = reader.read_slice(32, "H256")?;
+
+        Ok(H256::from_slice(data))
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        writer.data.extend_from_slice(value.as_bytes());
+    }
+
+    fn has_static_size() -> bool {
+        true
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("bytes32".into())
+    }
+}
+
+impl EvmData for Address {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        let data = reader.read_slice(32, "H160")?;
+
+        Ok(H160::from_slice(&data[12..32]).into())
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        H256::write(writer, value.0.into());
+    }
+
+    fn has_static_size() -> bool {
+        true
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("address".into())
+    }
+}
+
+impl EvmData for H160 {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        Ok(Address::read(reader)?.0)
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        Address::write(writer, value.into());
+    }
+
+    fn has_static_size() -> bool {
+        true
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("address".into())
+    }
+}
+
+impl EvmData for NonZeroAddress {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        let address = read_strict_address(reader)?;
+
+        if address.is_zero() {
+            return Err(RevertReason::custom("expected non-zero address").into());
+        }
+
+        Ok(NonZeroAddress(address))
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        Address::write(writer, value.into());
+    }
+
+    fn has_static_size() -> bool {
+        true
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("address".into())
+    }
+}
+
+impl EvmData for StrictAddress {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        Ok(StrictAddress(read_strict_address(reader)?))
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        Address::write(writer, value.into());
+    }
+
+    fn has_static_size() -> bool {
+        true
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("address".into())
+    }
+}
+
+impl EvmData for PayableAddress {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        Ok(Address::read(reader)?.into())
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        Address::write(writer, value.into());
+    }
+
+    fn has_static_size() -> bool {
+        true
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("address".into())
+    }
+}
+
+/// Read an address, rejecting words whose upper 12 bytes are not zero.
+fn read_strict_address(reader: &mut EvmDataReader) -> EvmResult<H160> {
+    let data = reader.read_slice(32, "H160")?;
+
+    if data[..12].iter().any(|b| *b != 0) {
+        return Err(RevertReason::custom("address has dirty high bytes").into());
+    }
+
+    Ok(H160::from_slice(&data[12..32]))
+}
+
+impl<const N: usize> EvmData for FixedBytes<N> {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SIZE;
+
+        let data = reader.read_slice(32, "bytesN")?;
 
-            // Append this data at the end of the current output.
-            output.append(&mut offset_datum.data);
+        if data[N..].iter().any(|b| *b != 0) {
+            return Err(RevertReason::custom("bytesN has non-zero padding").into());
         }
-    }
 
-    /// Write arbitrary bytes.
-    /// Doesn't handle any alignement checks, prefer using `write` instead if possible.
-    fn write_raw_bytes(mut self, value: &[u8]) -> Self {
-        self.data.extend_from_slice(value);
-        self
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(&data[..N]);
+
+        Ok(Self(bytes))
     }
 
-    /// Write data of requested type.
-    pub fn write<T: EvmData>(mut self, value: T) -> Self {
-        T::write(&mut self, value);
-        self
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SIZE;
+
+        let mut buffer = [0u8; 32];
+        buffer[..N].copy_from_slice(&value.0);
+        writer.data.extend_from_slice(&buffer);
     }
 
-    /// Writes a pointer to given data.
-    /// The data will be appended when calling `build`.
-    /// Initially write a dummy value as offset in this writer's data, which will be replaced by
-    /// the correct offset once the pointed data is appended.
-    ///
-    /// Takes `&mut self` since its goal is to be used inside `EvmData` impl and not in chains.
-    pub fn write_pointer(&mut self, data: Vec<u8>) {
-        let offset_position = self.data.len();
-        H256::write(self, H256::repeat_byte(0xff));
+    fn has_static_size() -> bool {
+        true
+    }
 
-        self.offset_data.push(OffsetDatum {
-            offset_position,
-            data,
-            offset_shift: 0,
-        });
+    fn solidity_type() -> Option<String> {
+        Some(format!("bytes{}", N))
     }
 }
 
-impl Default for EvmDataWriter {
-    fn default() -> Self {
-        Self::new()
+impl EvmData for Amount {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        Ok(Self(U256::read(reader)?))
     }
-}
 
-/// Data that can be converted from and to EVM data types.
-pub trait EvmData: Sized {
-    fn read(reader: &mut EvmDataReader) -> EvmResult<Self>;
-    fn write(writer: &mut EvmDataWriter, value: Self);
-    fn has_static_size() -> bool;
-}
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        U256::write(writer, value.0);
+    }
 
-#[impl_for_tuples(1, 18)]
-impl EvmData for Tuple {
     fn has_static_size() -> bool {
-        for_tuples!(#( Tuple::has_static_size() )&*)
+        true
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("uint256".into())
     }
+}
 
+impl EvmData for Function {
     fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
-        if !Self::has_static_size() {
-            let reader = &mut reader.read_pointer()?;
-            Ok(for_tuples!( ( #( reader.read::<Tuple>()? ),* ) ))
-        } else {
-            Ok(for_tuples!( ( #( reader.read::<Tuple>()? ),* ) ))
+        let data = reader.read_slice(32, "function")?;
+
+        // Reject non-zero padding instead of silently ignoring it.
+        let (value, padding) = data.split_at(24);
+        if padding.iter().any(|byte| *byte != 0) {
+            return Err(RevertReason::custom("invalid function encoding").into());
         }
+
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&value[20..]);
+
+        Ok(Self {
+            address: H160::from_slice(&value[..20]),
+            selector,
+        })
     }
 
     fn write(writer: &mut EvmDataWriter, value: Self) {
-        if !Self::has_static_size() {
-            let mut inner_writer = EvmDataWriter::new();
-            for_tuples!( #( Tuple::write(&mut inner_writer, value.Tuple); )* );
-            writer.write_pointer(inner_writer.build());
-        } else {
-            for_tuples!( #( Tuple::write(writer, value.Tuple); )* );
-        }
+        let mut buffer = [0u8; 32];
+        buffer[..20].copy_from_slice(value.address.as_bytes());
+        buffer[20..24].copy_from_slice(&value.selector);
+        writer.data.extend_from_slice(&buffer);
     }
-}
 
-impl EvmData for H256 {
-    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
-        let range = reader.move_cursor(32)?;
+    fn has_static_size() -> bool {
+        true
+    }
 
-        let data = reader
-            .input
-            .get(range)
-            .ok_or_else(|| revert("tried to parse H256 out of bounds"))?;
+    fn solidity_type() -> Option<String> {
+        Some("function".into())
+    }
+}
 
-        Ok(H256::from_slice(data))
+impl<const DECIMALS: u32> EvmData for Scaled<DECIMALS> {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        Ok(Self(U256::read(reader)?))
     }
 
     fn write(writer: &mut EvmDataWriter, value: Self) {
-        writer.data.extend_from_slice(value.as_bytes());
+        U256::write(writer, value.0);
     }
 
     fn has_static_size() -> bool {
         true
     }
+
+    fn solidity_type() -> Option<String> {
+        Some("uint256".into())
+    }
 }
 
-impl EvmData for Address {
+impl<const M: usize, const N: usize> EvmData for UFixed<M, N> {
     fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
-        let range = reader.move_cursor(32)?;
-
-        let data = reader
-            .input
-            .get(range)
-            .ok_or_else(|| revert("tried to parse H160 out of bounds"))?;
-
-        Ok(H160::from_slice(&data[12..32]).into())
+        Self::from_mantissa(U256::read(reader)?)
     }
 
     fn write(writer: &mut EvmDataWriter, value: Self) {
-        H256::write(writer, value.0.into());
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SIZE;
+
+        U256::write(writer, value.0);
     }
 
     fn has_static_size() -> bool {
         true
     }
+
+    fn solidity_type() -> Option<String> {
+        Some(format!("ufixed{}x{}", M, N))
+    }
 }
 
 impl EvmData for U256 {
     fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
-        let range = reader.move_cursor(32)?;
-
-        let data = reader
-            .input
-            .get(range)
-            .ok_or_else(|| revert("tried to parse U256 out of bounds"))?;
+        let data = reader.read_slice(32, "U256")?;
 
         Ok(U256::from_big_endian(data))
     }
@@ -413,6 +1650,10 @@ impl EvmData for U256 {
     fn has_static_size() -> bool {
         true
     }
+
+    fn solidity_type() -> Option<String> {
+        Some("uint256".into())
+    }
 }
 
 macro_rules! impl_evmdata_for_uints {
@@ -420,17 +1661,18 @@ macro_rules! impl_evmdata_for_uints {
 		$(
 			impl EvmData for $uint {
 				fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
-					let range = reader.move_cursor(32)?;
+					let data = reader.read_slice(32, core::any::type_name::<Self>())?;
 
-					let data = reader
-						.input
-						.get(range)
-						.ok_or_else(|| revert(alloc::format!(
-							"tried to parse {} out of bounds", core::any::type_name::<Self>()
-						)))?;
+					// Reject values that don't fit instead of silently truncating them.
+					let (high, low) = data.split_at(32 - core::mem::size_of::<Self>());
+					if high.iter().any(|byte| *byte != 0) {
+						return Err(RevertReason::custom(alloc::format!(
+							"value too large for uint{}", core::mem::size_of::<Self>() * 8
+						)).into());
+					}
 
 					let mut buffer = [0u8; core::mem::size_of::<Self>()];
-					buffer.copy_from_slice(&data[32 - core::mem::size_of::<Self>()..]);
+					buffer.copy_from_slice(low);
 					Ok(Self::from_be_bytes(buffer))
 				}
 
@@ -443,43 +1685,121 @@ macro_rules! impl_evmdata_for_uints {
 				fn has_static_size() -> bool {
 					true
 				}
+
+				fn solidity_type() -> Option<String> {
+					Some(format!("uint{}", core::mem::size_of::<Self>() * 8))
+				}
 			}
 		)*
 	};
 }
 
-impl_evmdata_for_uints!(u16, u32, u64, u128,);
+impl_evmdata_for_uints!(u8, u16, u32, u64, u128,);
 
-// The implementation for u8 is specific, for performance reasons.
-impl EvmData for u8 {
-    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
-        let range = reader.move_cursor(32)?;
+macro_rules! impl_evmdata_for_ints {
+	($($int:ty, )*) => {
+		$(
+			impl From<$int> for I256 {
+				fn from(value: $int) -> Self {
+					// Sign-extend to the full 32 bytes.
+					let mut buffer = if value < 0 { [0xffu8; 32] } else { [0u8; 32] };
+					buffer[32 - core::mem::size_of::<$int>()..].copy_from_slice(&value.to_be_bytes());
+					I256(U256::from_big_endian(&buffer))
+				}
+			}
 
-        let data = reader
-            .input
-            .get(range)
-            .ok_or_else(|| revert("tried to parse u64 out of bounds"))?;
+			impl TryFrom<I256> for $int {
+				type Error = RevertReason;
+
+				fn try_from(value: I256) -> Result<Self, Self::Error> {
+					const SIZE: usize = core::mem::size_of::<$int>();
+
+					let mut word = [0u8; 32];
+					value.0.to_big_endian(&mut word);
+
+					// All the truncated bytes must be a sign-extension of the kept ones.
+					let sign_byte = if word[32 - SIZE] & 0x80 == 0 { 0x00 } else { 0xff };
+					if word[..32 - SIZE].iter().any(|byte| *byte != sign_byte) {
+						return Err(RevertReason::custom(alloc::format!(
+							"value out of range for int{}", SIZE * 8
+						)));
+					}
+
+					let mut buffer = [0u8; SIZE];
+					buffer.copy_from_slice(&word[32 - SIZE..]);
+					Ok(Self::from_be_bytes(buffer))
+				}
+			}
+
+			impl EvmData for $int {
+				fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+					let data = reader.read_slice(32, core::any::type_name::<Self>())?;
+					let value = I256(U256::from_big_endian(data));
+
+					Ok(Self::try_from(value)?)
+				}
+
+				fn write(writer: &mut EvmDataWriter, value: Self) {
+					I256::write(writer, value.into());
+				}
+
+				fn has_static_size() -> bool {
+					true
+				}
+
+				fn solidity_type() -> Option<String> {
+					Some(format!("int{}", core::mem::size_of::<Self>() * 8))
+				}
+			}
+		)*
+	};
+}
 
-        Ok(data[31])
+impl_evmdata_for_ints!(i8, i16, i32, i64, i128,);
+
+impl EvmData for I256 {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        let data = reader.read_slice(32, "I256")?;
+
+        Ok(I256(U256::from_big_endian(data)))
     }
 
     fn write(writer: &mut EvmDataWriter, value: Self) {
-        let mut buffer = [0u8; 32];
-        buffer[31] = value;
+        U256::write(writer, value.0);
+    }
 
-        writer.data.extend_from_slice(&buffer);
+    fn has_static_size() -> bool {
+        true
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("int256".into())
+    }
+}
+
+impl EvmData for BalanceDelta {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        Ok(Self(I256::read(reader)?))
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        I256::write(writer, value.0);
     }
 
     fn has_static_size() -> bool {
         true
     }
+
+    fn solidity_type() -> Option<String> {
+        Some("int256".into())
+    }
 }
 
 impl EvmData for bool {
     fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
-        let h256 = H256::read(reader).map_err(|_| revert("tried to parse bool out of bounds"))?;
+        let data = reader.read_slice(32, "bool")?;
 
-        Ok(!h256.is_zero())
+        Ok(data.iter().any(|byte| *byte != 0))
     }
 
     fn write(writer: &mut EvmDataWriter, value: Self) {
@@ -494,33 +1814,71 @@ impl EvmData for bool {
     fn has_static_size() -> bool {
         true
     }
+
+    fn solidity_type() -> Option<String> {
+        Some("bool".into())
+    }
 }
 
-impl<T: EvmData> EvmData for Vec<T> {
+impl EvmData for StrictBool {
     fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
-        let mut inner_reader = reader.read_pointer()?;
+        let data = reader.read_slice(32, "bool")?;
 
-        let array_size: usize = inner_reader
-            .read::<U256>()
-            .map_err(|_| revert("tried to parse array length out of bounds"))?
-            .try_into()
-            .map_err(|_| revert("array length is too large"))?;
+        let (high, low) = data.split_at(31);
+        match (high.iter().all(|byte| *byte == 0), low[0]) {
+            (true, 0) => Ok(Self(false)),
+            (true, 1) => Ok(Self(true)),
+            _ => Err(RevertReason::custom("invalid bool encoding").into()),
+        }
+    }
 
-        let mut array = vec![];
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        bool::write(writer, value.0);
+    }
 
-        let mut item_reader = EvmDataReader {
-            input: inner_reader
-                .input
-                .get(32..)
-                .ok_or_else(|| revert("try to read array items out of bound"))?,
-            cursor: 0,
+    fn has_static_size() -> bool {
+        true
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("bool".into())
+    }
+}
+
+/// Read a dynamic array, rejecting it before allocating if it has more than `max_len` items.
+fn read_array<T: EvmData>(reader: &mut EvmDataReader, max_len: Option<usize>) -> EvmResult<Vec<T>> {
+    reader.read_pointed(|inner_reader| {
+        let array_size: usize = U256::from_big_endian(inner_reader.read_slice(32, "array length")?)
+            .try_into()
+            .map_err(|_| RevertReason::value_is_too_large("array length"))?;
+
+        let mut array = match max_len {
+            Some(max_len) if array_size > max_len => {
+                return Err(
+                    RevertReason::custom(format!("array has more than {} items", max_len)).into(),
+                )
+            }
+            Some(_) => Vec::with_capacity(array_size),
+            None => vec![],
         };
 
+        let mut item_reader = inner_reader
+            .sub_reader(32)
+            .ok_or_else(|| RevertReason::read_out_of_bounds("array items"))?;
+
         for _ in 0..array_size {
             array.push(item_reader.read()?);
         }
 
+        inner_reader.merge_reads(&item_reader);
+
         Ok(array)
+    })
+}
+
+impl<T: EvmData> EvmData for Vec<T> {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        read_array(reader, None)
     }
 
     fn write(writer: &mut EvmDataWriter, value: Self) {
@@ -548,30 +1906,115 @@ impl<T: EvmData> EvmData for Vec<T> {
     fn has_static_size() -> bool {
         false
     }
+
+    fn solidity_type() -> Option<String> {
+        Some(format!("{}[]", T::solidity_type()?))
+    }
 }
 
-impl EvmData for Bytes {
+/// ABI has no optional values, thus an `Option<T>` is encoded as the tuple
+/// `(bool present, T value)`. `None` is encoded with a default (zeroed) value, such that the
+/// encoding has the same size whether a value is present or not.
+impl<T: EvmData + Default> EvmData for Option<T> {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        let (present, value): (bool, T) = reader.read()?;
+
+        Ok(if present { Some(value) } else { None })
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        <(bool, T)>::write(writer, (value.is_some(), value.unwrap_or_default()));
+    }
+
+    fn has_static_size() -> bool {
+        T::has_static_size()
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some(format!("(bool,{})", T::solidity_type()?))
+    }
+}
+
+impl<T: EvmData, const N: usize> EvmData for [T; N] {
     fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
-        let mut inner_reader = reader.read_pointer()?;
+        fn read_items<T: EvmData, const N: usize>(reader: &mut EvmDataReader) -> EvmResult<[T; N]> {
+            let mut array: Vec<T> = Vec::with_capacity(N);
+            for _ in 0..N {
+                array.push(reader.read()?);
+            }
+
+            array
+                .try_into()
+                .map_err(|_| RevertReason::custom("fixed array length mismatch").into())
+        }
+
+        // Fixed arrays of static types are packed inline, while fixed arrays of
+        // dynamic types are themselves dynamic and thus behind a pointer.
+        if Self::has_static_size() {
+            read_items::<T, N>(reader)
+        } else {
+            reader.read_pointed(read_items::<T, N>)
+        }
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        if Self::has_static_size() {
+            for item in value {
+                T::write(writer, item);
+            }
+        } else {
+            let mut inner_writer = EvmDataWriter::new();
+            for item in value {
+                T::write(&mut inner_writer, item);
+            }
+            writer.write_pointer(inner_writer.build());
+        }
+    }
 
+    fn has_static_size() -> bool {
+        T::has_static_size()
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some(format!("{}[{}]", T::solidity_type()?, N))
+    }
+}
+
+/// Read the data of a `bytes`/`string` behind a pointer.
+/// If provided, the length is checked against `max_length` before accessing the data.
+fn read_bytes<'a>(
+    reader: &mut EvmDataReader<'a>,
+    max_length: Option<usize>,
+) -> EvmResult<&'a [u8]> {
+    reader.read_pointed(|inner_reader| {
         // Read bytes/string size.
-        let array_size: usize = inner_reader
-            .read::<U256>()
-            .map_err(|_| revert("tried to parse bytes/string length out of bounds"))?
-            .try_into()
-            .map_err(|_| revert("bytes/string length is too large"))?;
+        let array_size: usize =
+            U256::from_big_endian(inner_reader.read_slice(32, "bytes/string length")?)
+                .try_into()
+                .map_err(|_| RevertReason::value_is_too_large("bytes/string length"))?;
+
+        if let Some(max_length) = max_length {
+            if array_size > max_length {
+                return Err(RevertReason::custom("bytes too long").into());
+            }
+        }
 
         // Get valid range over the bytes data.
-        let range = inner_reader.move_cursor(array_size)?;
+        let data = inner_reader.read_slice(array_size, "bytes/string")?;
 
-        let data = inner_reader
-            .input
-            .get(range)
-            .ok_or_else(|| revert("tried to parse bytes/string out of bounds"))?;
+        // The data is padded to a multiple of 32 bytes.
+        let padding = (32 - array_size % 32) % 32;
+        inner_reader.mark_read(inner_reader.cursor + padding);
+
+        Ok(data)
+    })
+}
 
-        let bytes = Self(data.to_owned());
+impl EvmData for Bytes {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        let data = read_bytes(reader, None)?;
 
-        Ok(bytes)
+        Ok(Self(data.to_owned()))
     }
 
     fn write(writer: &mut EvmDataWriter, value: Self) {
@@ -600,4 +2043,54 @@ impl EvmData for Bytes {
     fn has_static_size() -> bool {
         false
     }
-}
\ No newline at end of file
+
+    fn solidity_type() -> Option<String> {
+        Some("bytes".into())
+    }
+}
+
+/// The `string` type of Solidity, which is validated to be UTF-8 when read.
+/// Encoded the same way as `Bytes`.
+impl EvmData for String {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        let bytes: Bytes = reader.read()?;
+
+        String::from_utf8(bytes.0)
+            .map_err(|_| RevertReason::custom("invalid UTF-8 in string").into())
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        Bytes::write(writer, Bytes(value.into_bytes()));
+    }
+
+    fn has_static_size() -> bool {
+        false
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("string".into())
+    }
+}
+
+impl<S: Get<u32>> EvmData for BoundedBytes<S> {
+    fn read(reader: &mut EvmDataReader) -> EvmResult<Self> {
+        let data = read_bytes(reader, Some(S::get() as usize))?;
+
+        Ok(Self {
+            inner: data.to_owned(),
+            _phantom: PhantomData,
+        })
+    }
+
+    fn write(writer: &mut EvmDataWriter, value: Self) {
+        Bytes::write(writer, Bytes(value.inner));
+    }
+
+    fn has_static_size() -> bool {
+        false
+    }
+
+    fn solidity_type() -> Option<String> {
+        Some("bytes".into())
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Conversion of balances between the decimals of the native currency and the decimals exposed
+//! to the EVM (usually 18).
+
+use crate::{EvmResult, RevertReason};
+
+use sp_core::U256;
+use sp_std::convert::TryInto;
+
+/// How to handle the digits lost when scaling a balance down to less decimals.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PrecisionLoss {
+    /// Drop the digits that can't be represented.
+    Truncate,
+    /// Revert if the balance can't be represented exactly.
+    Reject,
+}
+
+/// Scale a native balance with `native_decimals` decimals to `evm_decimals` decimals.
+/// Reverts if the result doesn't fit in 256 bits, or on precision loss if rejected.
+pub fn scale_to_evm(
+    native: u128,
+    native_decimals: u8,
+    evm_decimals: u8,
+    precision_loss: PrecisionLoss,
+) -> EvmResult<U256> {
+    scale(native.into(), native_decimals, evm_decimals, precision_loss)
+}
+
+/// Scale an EVM balance with `evm_decimals` decimals to `native_decimals` decimals.
+/// Reverts if the result doesn't fit in 128 bits, or on precision loss if rejected.
+pub fn scale_from_evm(
+    evm: U256,
+    native_decimals: u8,
+    evm_decimals: u8,
+    precision_loss: PrecisionLoss,
+) -> EvmResult<u128> {
+    scale(evm, evm_decimals, native_decimals, precision_loss)?
+        .try_into()
+        .map_err(|_| RevertReason::custom("amount overflow").into())
+}
+
+fn scale(value: U256, from: u8, to: u8, precision_loss: PrecisionLoss) -> EvmResult<U256> {
+    let factor = |decimals: u8| {
+        U256::from(10u8)
+            .checked_pow(decimals.into())
+            .ok_or_else(|| RevertReason::custom("amount overflow"))
+    };
+
+    if to >= from {
+        // 10^78 overflows, but 0 scaled up by any factor is still 0.
+        if value.is_zero() {
+            return Ok(U256::zero());
+        }
+
+        value
+            .checked_mul(factor(to - from)?)
+            .ok_or_else(|| RevertReason::custom("amount overflow").into())
+    } else {
+        // 10^78 overflows, but any value scaled down by that much is 0.
+        let factor = match factor(from - to) {
+            Ok(factor) => factor,
+            Err(_) if value.is_zero() || precision_loss == PrecisionLoss::Truncate => {
+                return Ok(U256::zero())
+            }
+            Err(_) => return Err(RevertReason::custom("amount precision loss").into()),
+        };
+
+        let (quotient, remainder) = value.div_mod(factor);
+        if !remainder.is_zero() && precision_loss == PrecisionLoss::Reject {
+            return Err(RevertReason::custom("amount precision loss").into());
+        }
+
+        Ok(quotient)
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! EIP-712 hashing of typed structured data, as used by EIP-2612 permits.
+
+use crate::{keccak256, Address, EvmDataWriter};
+
+use alloc::vec::Vec;
+use sp_core::{H160, H256, U256};
+
+/// Type hash of the EIP-712 domain with its usual fields.
+pub const DOMAIN_TYPE_HASH: H256 = H256(keccak256!(
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)"
+));
+
+/// Domain separator of an EIP-712 domain with a name, a version, a chain id and a verifying
+/// contract.
+pub fn domain_separator(
+    name: &str,
+    version: &str,
+    chain_id: u64,
+    verifying_contract: H160,
+) -> H256 {
+    hash_struct(
+        DOMAIN_TYPE_HASH,
+        &EvmDataWriter::new()
+            .write(keccak256(name.as_bytes()))
+            .write(keccak256(version.as_bytes()))
+            .write(U256::from(chain_id))
+            .write(Address(verifying_contract))
+            .build(),
+    )
+}
+
+/// Hash of a struct of type `type_hash`, whose fields are already encoded according to
+/// EIP-712 (dynamic values and structs being replaced by their hash).
+pub fn hash_struct(type_hash: H256, encoded_fields: &[u8]) -> H256 {
+    let mut data = Vec::with_capacity(32 + encoded_fields.len());
+    data.extend_from_slice(type_hash.as_bytes());
+    data.extend_from_slice(encoded_fields);
+
+    keccak256(&data)
+}
+
+/// Digest of a struct in a domain, which is signed by the user.
+pub fn digest(domain_separator: H256, struct_hash: H256) -> H256 {
+    let mut data = Vec::with_capacity(2 + 32 + 32);
+    data.extend_from_slice(b"\x19\x01");
+    data.extend_from_slice(domain_separator.as_bytes());
+    data.extend_from_slice(struct_hash.as_bytes());
+
+    keccak256(&data)
+}
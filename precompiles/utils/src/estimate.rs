@@ -0,0 +1,48 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Estimation of the gas costs recorded by the recording API, without recording them.
+//! Allows precompiles to report accurate gas estimates, for example in view functions.
+
+use crate::{LogExt, RuntimeHelper};
+
+use pallet_evm::Log;
+
+/// Cost recorded by `PrecompileHandleExt::record_log_costs_manual`.
+pub fn log_costs_manual(topics: usize, data_len: usize) -> u64 {
+    crate::log_costs(topics, data_len)
+}
+
+/// Cost recorded by `PrecompileHandleExt::record_log_costs`.
+/// Saturates to `u64::MAX` on overflow, such a cost failing to be recorded.
+pub fn log_costs(logs: &[&Log]) -> u64 {
+    logs.iter().fold(0u64, |total, log| {
+        total.saturating_add(log.compute_cost().unwrap_or(u64::MAX))
+    })
+}
+
+/// Cost recorded by `Gasometer::record_db_read`.
+pub fn db_read<Runtime: pallet_evm::Config>() -> u64 {
+    RuntimeHelper::<Runtime>::db_read_gas_cost()
+}
+
+/// Cost recorded by `Gasometer::record_db_write`.
+pub fn db_write<Runtime: pallet_evm::Config>() -> u64 {
+    RuntimeHelper::<Runtime>::db_write_gas_cost()
+}
@@ -0,0 +1,117 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{log_costs, out_of_gas, EvmResult, RuntimeHelper};
+
+use fp_evm::PrecompileHandle;
+
+/// Wrapper around a `PrecompileHandle` centralizing the gas accounting of a precompile.
+/// Every cost is checked against the remaining gas before being recorded, and the total
+/// recorded cost is tracked with overflow checks.
+///
+/// Precompiles processing a variable amount of items can use `record_iteration` to process
+/// as many items as the gas allows instead of running out of gas in the middle, while keeping
+/// a reserve of gas (see `with_reserve`) for the bookkeeping done after the loop.
+pub struct Gasometer<'a, H: PrecompileHandle> {
+    handle: &'a mut H,
+    used_gas: u64,
+    reserve: u64,
+}
+
+impl<'a, H: PrecompileHandle> Gasometer<'a, H> {
+    /// Create a new gasometer recording costs into provided handle.
+    pub fn new(handle: &'a mut H) -> Self {
+        Self {
+            handle,
+            used_gas: 0,
+            reserve: 0,
+        }
+    }
+
+    /// Keep `reserve` gas which iterations can't use, see `can_afford`.
+    pub fn with_reserve(mut self, reserve: u64) -> Self {
+        self.reserve = reserve;
+        self
+    }
+
+    /// Access the wrapped handle.
+    pub fn handle(&mut self) -> &mut H {
+        self.handle
+    }
+
+    /// Gas recorded through this gasometer.
+    pub fn used_gas(&self) -> u64 {
+        self.used_gas
+    }
+
+    /// Gas remaining in the wrapped handle.
+    pub fn remaining_gas(&self) -> u64 {
+        self.handle.remaining_gas()
+    }
+
+    /// Record an arbitrary cost.
+    /// Fails with `OutOfGas` if the cost exceeds the remaining gas.
+    pub fn record_cost(&mut self, cost: u64) -> EvmResult {
+        if cost > self.remaining_gas() {
+            return Err(out_of_gas());
+        }
+
+        let used_gas = self.used_gas.checked_add(cost).ok_or_else(out_of_gas)?;
+
+        self.handle.record_cost(cost)?;
+        self.used_gas = used_gas;
+
+        Ok(())
+    }
+
+    /// Whether an iteration costing `cost` can be afforded while keeping the reserve.
+    pub fn can_afford(&self, cost: u64) -> bool {
+        match cost.checked_add(self.reserve) {
+            Some(required) => required <= self.remaining_gas(),
+            None => false,
+        }
+    }
+
+    /// Record the cost of an iteration if it can be afforded while keeping the reserve.
+    /// Returns whether the cost has been recorded, in which case the iteration can proceed.
+    pub fn record_iteration(&mut self, cost: u64) -> EvmResult<bool> {
+        if !self.can_afford(cost) {
+            return Ok(false);
+        }
+
+        self.record_cost(cost)?;
+
+        Ok(true)
+    }
+
+    /// Record the cost of a log with provided amount of topics and data length.
+    pub fn record_log(&mut self, topics: usize, data_len: usize) -> EvmResult {
+        self.record_cost(log_costs(topics, data_len))
+    }
+
+    /// Record the cost of a Substrate DB read.
+    pub fn record_db_read<Runtime: pallet_evm::Config>(&mut self) -> EvmResult {
+        self.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())
+    }
+
+    /// Record the cost of a Substrate DB write.
+    pub fn record_db_write<Runtime: pallet_evm::Config>(&mut self) -> EvmResult {
+        self.record_cost(RuntimeHelper::<Runtime>::db_write_gas_cost())
+    }
+}
@@ -19,30 +19,60 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 extern crate alloc;
+// Allows the code generated by the derive macros to be used inside this crate.
+extern crate self as precompile_utils;
 
 use crate::alloc::borrow::ToOwned;
 use fp_evm::{
-    Context, ExitError, ExitRevert, ExitSucceed, PrecompileFailure, PrecompileHandle,
-    PrecompileOutput,
+    Context, ExitError, ExitReason, ExitRevert, ExitSucceed, PrecompileFailure, PrecompileHandle,
+    PrecompileOutput, Transfer,
 };
 use frame_support::{
-    dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo},
-    traits::Get,
+    dispatch::{DispatchInfo, Dispatchable, GetDispatchInfo, Pays, PostDispatchInfo},
+    traits::{Currency, ExistenceRequirement, Get},
+    weights::Weight,
 };
-use pallet_evm::{GasWeightMapping, Log};
+use pallet_evm::{AddressMapping, GasWeightMapping, Log};
 use sp_core::{H160, H256, U256};
 use sp_std::{marker::PhantomData, vec, vec::Vec};
 
+pub mod batch;
 mod data;
+pub mod decimals;
+pub mod eip712;
+pub mod estimate;
+mod gasometer;
+pub mod packed;
+pub mod reentrancy;
+pub mod revert;
+pub mod subcall;
 
-pub use data::{Address, Bytes, EvmData, EvmDataReader, EvmDataWriter};
-pub use precompile_utils_macro::{generate_function_selector, keccak256};
+pub use data::{
+    Address, Amount, BalanceDelta, BoundedBytes, Bytes, EvmData, EvmDataReader, EvmDataWriter,
+    FixedBytes, Function, NonZeroAddress, PayableAddress, PointerSlot, ReturnBuilder, Scaled,
+    Selector, StrictAddress, StrictBool, UFixed, DEFAULT_MAX_POINTER_DEPTH, I256,
+};
+pub use gasometer::Gasometer;
+pub use precompile_utils_macro::{
+    dispatch, function_selector, generate_function_selector, keccak256, EvmData, EvmEvent,
+};
+pub use revert::RevertReason;
 
-#[cfg(feature = "testing")]
+#[cfg(test)]
+mod mock;
+#[cfg(any(feature = "testing", test))]
 pub mod testing;
 #[cfg(test)]
 mod tests;
 
+#[doc(hidden)]
+/// Items used by the code generated by the derive macros.
+pub mod __private {
+    pub use alloc::{format, string::String};
+    pub use pallet_evm::Log;
+    pub use sp_core::{H160, H256};
+}
+
 /// Alias for Result returning an EVM precompile error.
 pub type EvmResult<T = ()> = Result<T, PrecompileFailure>;
 
@@ -51,11 +81,25 @@ pub type EvmResult<T = ()> = Result<T, PrecompileFailure>;
 /// consumed all the gas limit and the error message is not easily
 /// retrievable.
 pub fn error<T: Into<alloc::borrow::Cow<'static, str>>>(text: T) -> PrecompileFailure {
+    error_other(text)
+}
+
+/// Return an `Other` error with provided (static) text.
+#[must_use]
+pub fn error_other<T: Into<alloc::borrow::Cow<'static, str>>>(text: T) -> PrecompileFailure {
     PrecompileFailure::Error {
         exit_status: ExitError::Other(text.into()),
     }
 }
 
+/// Return an `OutOfGas` error.
+#[must_use]
+pub fn out_of_gas() -> PrecompileFailure {
+    PrecompileFailure::Error {
+        exit_status: ExitError::OutOfGas,
+    }
+}
+
 /// Builder for PrecompileOutput.
 #[derive(Clone, Debug)]
 pub struct LogsBuilder {
@@ -136,6 +180,56 @@ impl LogsBuilder {
             data: data.into(),
         }
     }
+
+    /// Start building a log whose amount of topics is only known at runtime.
+    #[must_use]
+    pub fn log(&self) -> LogBuilder {
+        LogBuilder {
+            address: self.address,
+            topics: vec![],
+            data: vec![],
+        }
+    }
+}
+
+/// Builder of a log with a dynamic amount of topics, see `LogsBuilder::log`.
+#[must_use]
+#[derive(Clone, Debug)]
+pub struct LogBuilder {
+    address: H160,
+    topics: Vec<H256>,
+    data: Vec<u8>,
+}
+
+impl LogBuilder {
+    /// Maximum amount of topics of a log.
+    pub const MAX_TOPICS: usize = 4;
+
+    /// Add a topic.
+    /// Reverts if the log already has `MAX_TOPICS` topics.
+    pub fn topic(mut self, topic: impl Into<H256>) -> EvmResult<Self> {
+        if self.topics.len() >= Self::MAX_TOPICS {
+            return Err(RevertReason::custom("log can't have more than 4 topics").into());
+        }
+
+        self.topics.push(topic.into());
+        Ok(self)
+    }
+
+    /// Set the data of the log.
+    pub fn data(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.data = data.into();
+        self
+    }
+
+    /// Create the log.
+    pub fn build(self) -> Log {
+        Log {
+            address: self.address,
+            topics: self.topics,
+            data: self.data,
+        }
+    }
 }
 
 /// Extension trait allowing to record logs into a PrecompileHandle.
@@ -152,8 +246,248 @@ impl LogExt for Log {
     }
 
     fn compute_cost(&self) -> EvmResult<u64> {
-        log_costs(self.topics.len(), self.data.len())
+        Ok(log_costs(self.topics.len(), self.data.len()))
+    }
+}
+
+/// Failure to record an external cost.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExternalCostError {
+    /// The handle doesn't meter external costs.
+    NotImplemented,
+    /// The cost exceeds what remains, or couldn't be recorded.
+    Exit(ExitError),
+}
+
+/// Extension of `PrecompileHandle` metering costs external to the EVM gas, such as the proof
+/// size (PoV) of Substrate calls.
+/// Handles opt in by implementing it, overriding the methods of the costs they meter. Costs
+/// which are not metered are charged as gas by the helpers using it, such as
+/// `RuntimeHelper::try_dispatch`.
+/// The `PrecompileHandle` of the Frontier version used here doesn't meter such costs, it can be
+/// wrapped in a `GasOnlyHandle` to be used with those helpers.
+pub trait ExternalCostHandle: PrecompileHandle {
+    /// Record external costs, failing if they exceed what remains.
+    fn record_external_cost(
+        &mut self,
+        _ref_time: Option<u64>,
+        _proof_size: Option<u64>,
+    ) -> Result<(), ExternalCostError> {
+        Err(ExternalCostError::NotImplemented)
+    }
+
+    /// Refund previously recorded external costs.
+    fn refund_external_cost(
+        &mut self,
+        _ref_time: Option<u64>,
+        _proof_size: Option<u64>,
+    ) -> Result<(), ExternalCostError> {
+        Err(ExternalCostError::NotImplemented)
+    }
+
+    /// Remaining proof size, if it is metered.
+    fn remaining_proof_size(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Handle metering only gas, wrapping a handle which doesn't implement `ExternalCostHandle`
+/// (such as the one of the EVM). External costs are charged as gas by the helpers using it.
+pub struct GasOnlyHandle<'a, H>(pub &'a mut H);
+
+impl<'a, H: PrecompileHandle> GasOnlyHandle<'a, H> {
+    /// Wrap provided handle.
+    pub fn new(handle: &'a mut H) -> Self {
+        Self(handle)
+    }
+}
+
+impl<H: PrecompileHandle> PrecompileHandle for GasOnlyHandle<'_, H> {
+    fn call(
+        &mut self,
+        address: H160,
+        transfer: Option<Transfer>,
+        input: Vec<u8>,
+        target_gas: Option<u64>,
+        is_static: bool,
+        context: &Context,
+    ) -> (ExitReason, Vec<u8>) {
+        self.0
+            .call(address, transfer, input, target_gas, is_static, context)
+    }
+
+    fn record_cost(&mut self, cost: u64) -> Result<(), ExitError> {
+        self.0.record_cost(cost)
+    }
+
+    fn remaining_gas(&self) -> u64 {
+        self.0.remaining_gas()
+    }
+
+    fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
+        self.0.log(address, topics, data)
+    }
+
+    fn code_address(&self) -> H160 {
+        self.0.code_address()
+    }
+
+    fn input(&self) -> &[u8] {
+        self.0.input()
+    }
+
+    fn context(&self) -> &Context {
+        self.0.context()
+    }
+
+    fn is_static(&self) -> bool {
+        self.0.is_static()
+    }
+
+    fn gas_limit(&self) -> Option<u64> {
+        self.0.gas_limit()
+    }
+}
+
+impl<H: PrecompileHandle> ExternalCostHandle for GasOnlyHandle<'_, H> {}
+
+/// Solidity event which can be emitted as a log.
+/// Can be derived with `#[derive(EvmEvent)]`.
+pub trait EvmEvent: Sized {
+    /// Signature of the event, such as `Transfer(address,address,uint256)`.
+    const SIGNATURE: &'static str;
+
+    /// First topic of the event logs, which is the Keccak256 hash of its signature.
+    const TOPIC: H256;
+
+    /// Build the log of the event emitted by provided address.
+    fn to_log(self, address: H160) -> Log;
+
+    /// Record the event as emitted by the precompile.
+    /// The cost of the log must be recorded separately.
+    fn record(self, handle: &mut impl PrecompileHandle) -> EvmResult {
+        let address = handle.context().address;
+        self.to_log(address).record(handle)
+    }
+}
+
+/// Encode a value type as an event topic.
+/// Value types (`address`, `uintN`, `bytesN`, ...) are used as topics directly, padded to 32
+/// bytes. Panics if the value is not encoded as a single 32 bytes word.
+pub fn encode_topic<T: EvmData>(value: T) -> H256 {
+    H256::from_slice(&EvmDataWriter::new().write(value).build())
+}
+
+/// Encode an indexed dynamic value (`string`, `bytes`) as an event topic.
+/// Solidity doesn't store these values in topics but their Keccak256 hash, computed over
+/// their raw content without length nor padding.
+pub fn indexed_dynamic(data: &[u8]) -> H256 {
+    indexed_dynamic_with::<SpIoKeccak>(data)
+}
+
+/// Encode an indexed dynamic value like `indexed_dynamic`, using the `H` Keccak256 backend.
+pub fn indexed_dynamic_with<H: KeccakHasher>(data: &[u8]) -> H256 {
+    keccak256_with::<H>(data)
+}
+
+/// Backend computing Keccak256 hashes at runtime.
+/// Allows chains to provide their own implementation.
+pub trait KeccakHasher {
+    fn keccak256(data: &[u8]) -> H256;
+}
+
+/// Keccak256 backend of `sp_io`, available in both `std` and `no_std`.
+pub struct SpIoKeccak;
+
+impl KeccakHasher for SpIoKeccak {
+    fn keccak256(data: &[u8]) -> H256 {
+        H256(sp_io::hashing::keccak_256(data))
+    }
+}
+
+/// Keccak256 hash of dynamic data, using the `sp_io` backend.
+/// Use the `keccak256!` macro to hash literals at compile time.
+pub fn keccak256(data: &[u8]) -> H256 {
+    keccak256_with::<SpIoKeccak>(data)
+}
+
+/// Keccak256 hash of dynamic data, using the `H` backend.
+pub fn keccak256_with<H: KeccakHasher>(data: &[u8]) -> H256 {
+    H::keccak256(data)
+}
+
+/// Half of the order of the secp256k1 curve.
+const SECP256K1_HALF_N: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Recover the address which signed `digest` with the secp256k1 signature `(v, r, s)`.
+/// `v` must be 27 or 28 like for the `ecrecover` precompile. Malleable signatures, whose
+/// `s` is in the upper half of the curve order, are rejected (EIP-2).
+/// Reverts with "invalid signature" if the signer can't be recovered.
+pub fn recover_signer(digest: H256, v: u8, r: H256, s: H256) -> EvmResult<H160> {
+    let recovery_id = match v {
+        27 | 28 => v - 27,
+        _ => return Err(revert_with_reason("invalid signature")),
+    };
+
+    if s.as_bytes() > &SECP256K1_HALF_N[..] {
+        return Err(revert_with_reason("invalid signature"));
+    }
+
+    let mut signature = [0u8; 65];
+    signature[..32].copy_from_slice(r.as_bytes());
+    signature[32..64].copy_from_slice(s.as_bytes());
+    signature[64] = recovery_id;
+
+    let public = sp_io::crypto::secp256k1_ecdsa_recover(&signature, digest.as_fixed_bytes())
+        .map_err(|_| revert_with_reason("invalid signature"))?;
+
+    Ok(H160::from_slice(&keccak256(&public).as_bytes()[12..]))
+}
+
+/// Parse an address from its `0x`-prefixed hex representation, checking its EIP-55 checksum
+/// if it has one. Addresses with digits of a single case (such as all lowercase) don't have a
+/// checksum and are accepted as is.
+pub fn parse_checksummed_address(s: &str) -> EvmResult<H160> {
+    let digits = s
+        .strip_prefix("0x")
+        .ok_or_else(|| revert_with_reason("address must start with 0x"))?
+        .as_bytes();
+
+    if digits.len() != 40 {
+        return Err(revert_with_reason("address must have 40 hex digits"));
+    }
+
+    let mut address = [0u8; 20];
+    for (i, digit) in digits.iter().enumerate() {
+        let nibble = (*digit as char)
+            .to_digit(16)
+            .ok_or_else(|| revert_with_reason("invalid hex digit in address"))?;
+        address[i / 2] |= (nibble as u8) << (if i % 2 == 0 { 4 } else { 0 });
+    }
+
+    let has_lowercase = digits.iter().any(u8::is_ascii_lowercase);
+    let has_uppercase = digits.iter().any(u8::is_ascii_uppercase);
+    if has_lowercase && has_uppercase {
+        // Letters are uppercase when the matching nibble of the hash of the lowercase
+        // address is 8 or more.
+        let hash = keccak256(&digits.to_ascii_lowercase());
+        for (i, digit) in digits.iter().enumerate() {
+            let hash_nibble = if i % 2 == 0 {
+                hash.as_bytes()[i / 2] >> 4
+            } else {
+                hash.as_bytes()[i / 2] & 0x0f
+            };
+
+            if digit.is_ascii_alphabetic() && digit.is_ascii_uppercase() != (hash_nibble >= 8) {
+                return Err(revert_with_reason("invalid address checksum"));
+            }
+        }
     }
+
+    Ok(H160(address))
 }
 
 /// Helper functions requiring a Runtime.
@@ -166,27 +500,134 @@ where
     Runtime: pallet_evm::Config,
     Runtime::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
 {
+    /// Gas that `try_dispatch` requires to dispatch provided call, computed from its
+    /// estimated weight without dispatching it.
+    /// The gas recorded by `try_dispatch` can be lower if the call reports its actual weight,
+    /// or higher if the handle doesn't meter proof size (see `proof_size_gas_cost`).
+    pub fn dispatch_gas_cost<Call: GetDispatchInfo>(call: &Call) -> u64 {
+        Runtime::GasWeightMapping::weight_to_gas(call.get_dispatch_info().weight)
+    }
+
+    /// Whether provided call is declared to pay fees, without dispatching it.
+    /// Calls declared to pay fees can still be made free when dispatched by returning
+    /// `Pays::No` in their `PostDispatchInfo`, this only matches the actual `pays_fee` of
+    /// calls that don't.
+    pub fn dispatch_pays_fee<Call: GetDispatchInfo>(call: &Call) -> Pays {
+        call.get_dispatch_info().pays_fee
+    }
+
     /// Try to dispatch a Substrate call.
     /// Return an error if there are not enough gas, or if the call fails.
     /// If successful returns the used gas using the Runtime GasWeightMapping.
+    /// The proof size of the call is recorded as an external cost, or as gas if the handle
+    /// doesn't meter it (see `proof_size_gas_cost`). The recorded proof size is the actual one,
+    /// even if it exceeds the estimation of the call.
     pub fn try_dispatch<Call>(
-        handle: &mut impl PrecompileHandleExt,
+        handle: &mut impl ExternalCostHandle,
+        origin: <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin,
+        call: Call,
+    ) -> EvmResult<()>
+    where
+        Runtime::RuntimeCall: From<Call>,
+    {
+        Self::try_dispatch_with_mapping::<Runtime::GasWeightMapping, _>(handle, origin, call)
+    }
+
+    /// Try to dispatch a Substrate call like `try_dispatch`, converting its weight to gas with
+    /// the `M` mapping instead of the Runtime `GasWeightMapping`.
+    /// Allows to discount (or surcharge) specific calls.
+    pub fn try_dispatch_with_mapping<M, Call>(
+        handle: &mut impl ExternalCostHandle,
+        origin: <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin,
+        call: Call,
+    ) -> EvmResult<()>
+    where
+        M: GasWeightMapping,
+        Runtime::RuntimeCall: From<Call>,
+    {
+        Self::dispatch_recording_gas::<M, _>(handle, origin, call, None)?;
+
+        Ok(())
+    }
+
+    /// Try to dispatch a Substrate call like `try_dispatch`, returning the `PostDispatchInfo`
+    /// of the call.
+    /// The gas is recorded even if the call returns `Pays::No`.
+    pub fn try_dispatch_with_info<Call>(
+        handle: &mut impl ExternalCostHandle,
         origin: <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin,
         call: Call,
+    ) -> EvmResult<PostDispatchInfo>
+    where
+        Runtime::RuntimeCall: From<Call>,
+    {
+        let (post_info, _) = Self::dispatch_recording_gas::<Runtime::GasWeightMapping, _>(
+            handle, origin, call, None,
+        )?;
+
+        Ok(post_info)
+    }
+
+    /// Try to dispatch a Substrate call like `try_dispatch`, using a pre-computed
+    /// `DispatchInfo` of the call instead of computing it again.
+    /// Allows to compute it once when dispatching many calls of the same shape. The gas is
+    /// recorded like `try_dispatch`, including using the actual weight of the call if it
+    /// reports one.
+    /// `dispatch_info` must be the one of `call`, otherwise the required gas is wrong.
+    pub fn try_dispatch_with_dispatch_info<Call>(
+        handle: &mut impl ExternalCostHandle,
+        origin: <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin,
+        call: Call,
+        dispatch_info: DispatchInfo,
     ) -> EvmResult<()>
+    where
+        Runtime::RuntimeCall: From<Call>,
+    {
+        Self::dispatch_recording_gas::<Runtime::GasWeightMapping, _>(
+            handle,
+            origin,
+            call,
+            Some(dispatch_info),
+        )?;
+
+        Ok(())
+    }
+
+    /// Dispatch a Substrate call and record its ref time as gas using the `M` mapping, and its
+    /// proof size as an external cost if the handle meters it or as gas otherwise, returning
+    /// its `PostDispatchInfo` and the weight it used.
+    /// The `DispatchInfo` of the call is computed if not provided.
+    /// Fails before dispatching if the estimated weight exceeds the remaining gas or proof
+    /// size.
+    fn dispatch_recording_gas<M: GasWeightMapping, Call>(
+        handle: &mut impl ExternalCostHandle,
+        origin: <Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin,
+        call: Call,
+        dispatch_info: Option<DispatchInfo>,
+    ) -> EvmResult<(PostDispatchInfo, Weight)>
     where
         Runtime::RuntimeCall: From<Call>,
     {
         let call = Runtime::RuntimeCall::from(call);
-        let dispatch_info = call.get_dispatch_info();
+        let dispatch_info = dispatch_info.unwrap_or_else(|| call.get_dispatch_info());
+
+        // Make sure there is enough proof size if it is metered, and enough gas otherwise.
+        let mut required_gas = M::weight_to_gas(dispatch_info.weight);
+        match handle.remaining_proof_size() {
+            Some(remaining_proof_size) => {
+                if dispatch_info.weight.proof_size() > remaining_proof_size {
+                    return Err(out_of_gas());
+                }
+            }
+            None => {
+                required_gas = required_gas
+                    .saturating_add(Self::proof_size_gas_cost(dispatch_info.weight.proof_size()));
+            }
+        }
 
         // Make sure there is enough gas.
-        let remaining_gas = handle.remaining_gas();
-        let required_gas = Runtime::GasWeightMapping::weight_to_gas(dispatch_info.weight);
-        if required_gas > remaining_gas {
-            return Err(PrecompileFailure::Error {
-                exit_status: ExitError::OutOfGas,
-            });
+        if required_gas > handle.remaining_gas() {
+            return Err(out_of_gas());
         }
 
         // Dispatch call.
@@ -194,18 +635,27 @@ where
         // However while Substrate handle checking weight while not making the sender pay for it,
         // the EVM doesn't. It seems this safer to always record the costs to avoid unmetered
         // computations.
-        let result = call
+        let post_info = call
             .dispatch(origin)
             .map_err(|e| revert(alloc::format!("Dispatched call failed with error: {:?}", e)))?;
 
-        let used_weight = result.actual_weight;
+        let used_weight = post_info.actual_weight.unwrap_or(dispatch_info.weight);
 
-        let used_gas =
-            Runtime::GasWeightMapping::weight_to_gas(used_weight.unwrap_or(dispatch_info.weight));
+        let used_gas = M::weight_to_gas(used_weight);
 
         handle.record_cost(used_gas)?;
 
-        Ok(())
+        match handle.record_external_cost(None, Some(used_weight.proof_size())) {
+            Ok(()) => (),
+            Err(ExternalCostError::NotImplemented) => {
+                handle.record_cost(Self::proof_size_gas_cost(used_weight.proof_size()))?;
+            }
+            Err(ExternalCostError::Exit(exit_status)) => {
+                return Err(PrecompileFailure::Error { exit_status });
+            }
+        }
+
+        Ok((post_info, used_weight))
     }
 }
 
@@ -226,10 +676,94 @@ where
             <Runtime as frame_system::Config>::DbWeight::get().reads(1),
         )
     }
+
+    /// Cost in gas of `proof_size` bytes of proof size (PoV), to meter it when the handle
+    /// doesn't meter external costs.
+    /// A byte costs the block gas limit divided by the maximum proof size of a block, such that
+    /// filling the proof of a block costs as much gas as filling the block.
+    pub fn proof_size_gas_cost(proof_size: u64) -> u64 {
+        if proof_size == 0 {
+            return 0;
+        }
+
+        let max_proof_size = <Runtime as frame_system::Config>::BlockWeights::get()
+            .max_block
+            .proof_size();
+        let gas_per_byte = <Runtime as pallet_evm::Config>::BlockGasLimit::get()
+            .checked_div(U256::from(max_proof_size))
+            .unwrap_or_else(U256::max_value);
+
+        gas_per_byte
+            .saturating_mul(U256::from(proof_size))
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Cost of `n` Substrate DB writes in gas.
+    /// The weight of the writes is converted at once, which is more accurate than multiplying
+    /// the cost of a single write as the conversion isn't necessarily linear.
+    pub fn db_write_gas_cost_n(n: u64) -> u64 {
+        <Runtime as pallet_evm::Config>::GasWeightMapping::weight_to_gas(
+            <Runtime as frame_system::Config>::DbWeight::get().writes(n),
+        )
+    }
+
+    /// Cost of `n` Substrate DB reads in gas.
+    /// The weight of the reads is converted at once, which is more accurate than multiplying
+    /// the cost of a single read as the conversion isn't necessarily linear.
+    pub fn db_read_gas_cost_n(n: u64) -> u64 {
+        <Runtime as pallet_evm::Config>::GasWeightMapping::weight_to_gas(
+            <Runtime as frame_system::Config>::DbWeight::get().reads(n),
+        )
+    }
+}
+
+/// Balance of the native currency of a runtime.
+pub type NativeBalanceOf<Runtime> = <<Runtime as pallet_evm::Config>::Currency as Currency<
+    <Runtime as frame_system::Config>::AccountId,
+>>::Balance;
+
+impl<Runtime> RuntimeHelper<Runtime>
+where
+    Runtime: pallet_evm::Config,
+    NativeBalanceOf<Runtime>: TryFrom<U256>,
+{
+    /// Transfer the value sent to the precompile to `dest`.
+    /// The EVM credits the value to the account of the precompile before executing it, from
+    /// which it is transferred. Records the cost of the DB accesses of the transfer.
+    /// Reverts without modifying any balance if the transfer fails.
+    pub fn deposit_value(handle: &mut impl PrecompileHandle, dest: H160) -> EvmResult {
+        let value = handle.context().apparent_value;
+        if value.is_zero() {
+            return Ok(());
+        }
+
+        // Both accounts are read and written.
+        handle.record_cost(
+            Self::db_read_gas_cost_n(2).saturating_add(Self::db_write_gas_cost_n(2)),
+        )?;
+
+        let amount: NativeBalanceOf<Runtime> = value
+            .try_into()
+            .map_err(|_| revert_with_reason("value is too large"))?;
+
+        let source = Runtime::AddressMapping::into_account_id(handle.context().address);
+        let dest = Runtime::AddressMapping::into_account_id(dest);
+
+        <Runtime::Currency as Currency<_>>::transfer(
+            &source,
+            &dest,
+            amount,
+            ExistenceRequirement::AllowDeath,
+        )
+        .map_err(|_| revert_with_reason("failed to transfer value"))?;
+
+        Ok(())
+    }
 }
 
 /// Represents modifiers a Solidity function can be annotated with.
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum FunctionModifier {
     /// Function that doesn't modify the state.
     View,
@@ -240,6 +774,28 @@ pub enum FunctionModifier {
     Payable,
 }
 
+impl FunctionModifier {
+    /// Modifier of a function with provided Solidity state mutability (`view`, `nonpayable` or
+    /// `payable`), such as the ones of the `stateMutability` of a Solidity ABI JSON.
+    /// `pure` functions are handled as views.
+    pub fn from_state_mutability(state_mutability: &str) -> Option<Self> {
+        match state_mutability {
+            "view" | "pure" => Some(Self::View),
+            "nonpayable" => Some(Self::NonPayable),
+            "payable" => Some(Self::Payable),
+            _ => None,
+        }
+    }
+}
+
+/// Weight of `count` Substrate DB reads of `read_proof_size` bytes of proof size each, on top of
+/// the weight of the reads in `Runtime::DbWeight`.
+fn db_reads_weight<Runtime: frame_system::Config>(count: u64, read_proof_size: u64) -> Weight {
+    <Runtime as frame_system::Config>::DbWeight::get()
+        .reads(count)
+        .saturating_add(Weight::from_parts(0, read_proof_size.saturating_mul(count)))
+}
+
 pub trait PrecompileHandleExt: PrecompileHandle {
     #[must_use]
     /// Record cost of a log manually.
@@ -248,53 +804,226 @@ pub trait PrecompileHandleExt: PrecompileHandle {
 
     #[must_use]
     /// Record cost of logs.
+    /// The total cost is recorded at once, thus nothing is recorded if any of the logs can't be
+    /// afforded.
     fn record_log_costs(&mut self, logs: &[&Log]) -> EvmResult;
 
+    #[must_use]
+    /// Record cost of a log manually using the schedule `S`, see `record_log_costs_manual`.
+    fn record_log_costs_manual_with<S: LogCostSchedule>(
+        &mut self,
+        topics: usize,
+        data_len: usize,
+    ) -> EvmResult;
+
+    #[must_use]
+    /// Record cost of logs using the schedule `S`, see `record_log_costs`.
+    fn record_log_costs_with<S: LogCostSchedule>(&mut self, logs: &[&Log]) -> EvmResult;
+
+    #[must_use]
+    /// Record the cost of expanding the memory from `current_words` to `new_words` 32 bytes
+    /// words, see `memory_expansion_cost`.
+    fn record_memory_expansion(&mut self, current_words: u64, new_words: u64) -> EvmResult;
+
+    #[must_use]
+    /// Record the cost of `count` Substrate DB reads of `read_proof_size` bytes of proof size
+    /// each, such as the maximum encoded length of the read item and its key.
+    /// The ref time of `Runtime::DbWeight` is recorded as gas, and the proof size (including the
+    /// one of `Runtime::DbWeight`) as an external cost. If the handle doesn't meter external
+    /// costs, the proof size is recorded as gas, see `RuntimeHelper::proof_size_gas_cost`.
+    fn record_db_read_cost<Runtime: pallet_evm::Config>(
+        &mut self,
+        count: u64,
+        read_proof_size: u64,
+    ) -> EvmResult
+    where
+        Self: ExternalCostHandle;
+
+    #[must_use]
+    /// Refund the proof size of `count` Substrate DB reads recorded with `record_db_read_cost`,
+    /// for example if less reads were done than expected.
+    /// Gas can't be refunded, thus nothing is refunded if the handle doesn't meter external
+    /// costs.
+    fn refund_db_read_cost<Runtime: pallet_evm::Config>(
+        &mut self,
+        count: u64,
+        read_proof_size: u64,
+    ) -> EvmResult
+    where
+        Self: ExternalCostHandle;
+
+    #[must_use]
+    /// Run `f` and return its result along with the gas it consumed, measured as the
+    /// difference of remaining gas before and after it. Only observes the gas, which is
+    /// useful for tests and logging. Errors of `f` are propagated.
+    fn measure_gas<R>(&mut self, f: impl FnOnce(&mut Self) -> EvmResult<R>) -> EvmResult<(R, u64)>;
+
     #[must_use]
     /// Check that a function call is compatible with the context it is
     /// called into.
     fn check_function_modifier(&self, modifier: FunctionModifier) -> EvmResult;
 
+    #[must_use]
+    /// Check that a function call is compatible with the context it is
+    /// called into, returning whether a non-zero value was sent.
+    /// Allows payable functions to handle calls without value differently.
+    fn check_function_modifier_value(&self, modifier: FunctionModifier) -> EvmResult<bool>;
+
+    #[must_use]
+    /// Check that exactly `expected` value has been sent, for fixed-price payable functions.
+    fn require_value(&self, expected: U256) -> EvmResult;
+
+    #[must_use]
+    /// Check that no value has been sent, like for a non-payable function.
+    fn require_no_value(&self) -> EvmResult;
+
+    #[must_use]
+    /// Check that the caller is one of the `allowed` addresses.
+    /// The caller is the one of the context (`msg.sender`), which is not necessarily the
+    /// transaction origin. In a DELEGATECALL context it is the caller of the contract that
+    /// delegated the call instead of that contract.
+    fn require_caller(&self, allowed: &[H160]) -> EvmResult;
+
     #[must_use]
     /// Read the selector from the input data.
     fn read_selector<T>(&self) -> EvmResult<T>
     where
         T: num_enum::TryFromPrimitive<Primitive = u32>;
 
+    #[must_use]
+    /// Read the selector from the input data, following the Solidity conventions for empty
+    /// input (`receive`) and unknown selectors (`fallback`).
+    fn read_selector_or_fallback<T>(&self) -> EvmResult<Selector<T>>
+    where
+        T: num_enum::TryFromPrimitive<Primitive = u32>;
+
+    #[must_use]
+    /// Peek the selector from the input data, without skipping it.
+    fn peek_selector<T>(&self) -> EvmResult<T>
+    where
+        T: num_enum::TryFromPrimitive<Primitive = u32>;
+
     #[must_use]
     /// Returns a reader of the input, skipping the selector.
     fn read_input(&self) -> EvmResult<EvmDataReader>;
+
+    #[must_use]
+    /// Caller of the precompile (`msg.sender`).
+    fn caller(&self) -> H160;
+
+    #[must_use]
+    /// Value sent to the precompile (`msg.value`).
+    fn apparent_value(&self) -> U256;
+
+    #[must_use]
+    /// Address of the precompile in the current context (`address(this)`).
+    fn address(&self) -> H160;
 }
 
-pub fn log_costs(topics: usize, data_len: usize) -> EvmResult<u64> {
+/// Gas schedule of logs, allowing chains to use other costs than Ethereum.
+/// The default values are the ones of Ethereum.
+pub trait LogCostSchedule {
     // Cost calculation is copied from EVM code that is not publicly exposed by the crates.
     // https://github.com/rust-blockchain/evm/blob/master/gasometer/src/costs.rs#L148
-
+    /// Base cost of a log.
     const G_LOG: u64 = 375;
+    /// Cost of each byte of data of a log.
     const G_LOGDATA: u64 = 8;
+    /// Cost of each topic of a log.
     const G_LOGTOPIC: u64 = 375;
+}
+
+/// Gas schedule of logs on Ethereum, used by `log_costs` and the functions not taking a
+/// schedule.
+pub struct EthereumLogCostSchedule;
 
-    let topic_cost = G_LOGTOPIC
+impl LogCostSchedule for EthereumLogCostSchedule {}
+
+/// Cost of a log with provided amount of topics and data length.
+/// Saturates to `u64::MAX` on overflow, such a cost failing to be recorded as any other cost
+/// exceeding the remaining gas.
+pub fn log_costs(topics: usize, data_len: usize) -> u64 {
+    log_costs_with::<EthereumLogCostSchedule>(topics, data_len)
+}
+
+/// Cost of a log with provided amount of topics and data length using the schedule `S`,
+/// see `log_costs`.
+pub fn log_costs_with<S: LogCostSchedule>(topics: usize, data_len: usize) -> u64 {
+    let topic_cost = S::G_LOGTOPIC.saturating_mul(topics as u64);
+    let data_cost = S::G_LOGDATA.saturating_mul(data_len as u64);
+
+    S::G_LOG
+        .saturating_add(topic_cost)
+        .saturating_add(data_cost)
+}
+
+/// Cost of a log with provided amount of topics and data length.
+/// Fails with `OutOfGas` on overflow.
+pub fn log_costs_checked(topics: usize, data_len: usize) -> EvmResult<u64> {
+    log_costs_checked_with::<EthereumLogCostSchedule>(topics, data_len)
+}
+
+/// Cost of a log with provided amount of topics and data length using the schedule `S`,
+/// see `log_costs_checked`.
+pub fn log_costs_checked_with<S: LogCostSchedule>(
+    topics: usize,
+    data_len: usize,
+) -> EvmResult<u64> {
+    let topic_cost = S::G_LOGTOPIC
         .checked_mul(topics as u64)
-        .ok_or(PrecompileFailure::Error {
-            exit_status: ExitError::OutOfGas,
-        })?;
+        .ok_or_else(out_of_gas)?;
 
-    let data_cost = G_LOGDATA
+    let data_cost = S::G_LOGDATA
         .checked_mul(data_len as u64)
-        .ok_or(PrecompileFailure::Error {
-            exit_status: ExitError::OutOfGas,
-        })?;
+        .ok_or_else(out_of_gas)?;
 
-    G_LOG
+    S::G_LOG
         .checked_add(topic_cost)
-        .ok_or(PrecompileFailure::Error {
-            exit_status: ExitError::OutOfGas,
-        })?
+        .ok_or_else(out_of_gas)?
         .checked_add(data_cost)
-        .ok_or(PrecompileFailure::Error {
-            exit_status: ExitError::OutOfGas,
-        })
+        .ok_or_else(out_of_gas)
+}
+
+/// Total cost of provided logs, as recorded by `record_log_costs`.
+/// Allows to check all the logs can be afforded before doing any work.
+pub fn log_costs_total(logs: &[&Log]) -> EvmResult<u64> {
+    log_costs_total_with::<EthereumLogCostSchedule>(logs)
+}
+
+/// Total cost of provided logs using the schedule `S`, see `log_costs_total`.
+pub fn log_costs_total_with<S: LogCostSchedule>(logs: &[&Log]) -> EvmResult<u64> {
+    logs.iter().try_fold(0u64, |total, log| {
+        total
+            .checked_add(log_costs_with::<S>(log.topics.len(), log.data.len()))
+            .ok_or_else(out_of_gas)
+    })
+}
+
+// https://github.com/rust-blockchain/evm/blob/master/gasometer/src/costs.rs#L175
+const G_MEMORY: u64 = 3;
+const MEMORY_QUADRATIC_DIVISOR: u64 = 512;
+
+/// Cost of a memory of `words` 32 bytes words.
+fn memory_cost(words: u64) -> Option<u64> {
+    let linear_cost = G_MEMORY.checked_mul(words)?;
+    let quadratic_cost = words.checked_mul(words)? / MEMORY_QUADRATIC_DIVISOR;
+
+    linear_cost.checked_add(quadratic_cost)
+}
+
+/// Cost of expanding the memory from `current_words` to `new_words` 32 bytes words, following
+/// the quadratic EVM memory cost formula. Expanding to a smaller memory is free.
+/// Saturates to `u64::MAX` on overflow, such a cost failing to be recorded as any other cost
+/// exceeding the remaining gas.
+pub fn memory_expansion_cost(current_words: u64, new_words: u64) -> u64 {
+    if new_words <= current_words {
+        return 0;
+    }
+
+    match (memory_cost(current_words), memory_cost(new_words)) {
+        (Some(current_cost), Some(new_cost)) => new_cost - current_cost,
+        _ => u64::MAX,
+    }
 }
 
 impl<T: PrecompileHandle> PrecompileHandleExt for T {
@@ -302,28 +1031,178 @@ impl<T: PrecompileHandle> PrecompileHandleExt for T {
     /// Record cost of a log manualy.
     /// This can be useful to record log costs early when their content have static size.
     fn record_log_costs_manual(&mut self, topics: usize, data_len: usize) -> EvmResult {
-        self.record_cost(log_costs(topics, data_len)?)?;
-
-        Ok(())
+        self.record_log_costs_manual_with::<EthereumLogCostSchedule>(topics, data_len)
     }
 
     #[must_use]
     /// Record cost of logs.
+    /// The total cost is recorded at once, thus nothing is recorded if any of the logs can't be
+    /// afforded.
     fn record_log_costs(&mut self, logs: &[&Log]) -> EvmResult {
-        for log in logs {
-            self.record_log_costs_manual(log.topics.len(), log.data.len())?;
+        self.record_log_costs_with::<EthereumLogCostSchedule>(logs)
+    }
+
+    #[must_use]
+    /// Record cost of a log manually using the schedule `S`, see `record_log_costs_manual`.
+    fn record_log_costs_manual_with<S: LogCostSchedule>(
+        &mut self,
+        topics: usize,
+        data_len: usize,
+    ) -> EvmResult {
+        self.record_cost(log_costs_with::<S>(topics, data_len))?;
+
+        Ok(())
+    }
+
+    #[must_use]
+    /// Record cost of logs using the schedule `S`, see `record_log_costs`.
+    fn record_log_costs_with<S: LogCostSchedule>(&mut self, logs: &[&Log]) -> EvmResult {
+        let cost = log_costs_total_with::<S>(logs)?;
+
+        // Check before recording, as a failing `record_cost` can consume all the gas.
+        if cost > self.remaining_gas() {
+            return Err(out_of_gas());
         }
 
+        self.record_cost(cost)?;
+
         Ok(())
     }
 
+    #[must_use]
+    /// Record the cost of expanding the memory from `current_words` to `new_words` 32 bytes
+    /// words, see `memory_expansion_cost`.
+    fn record_memory_expansion(&mut self, current_words: u64, new_words: u64) -> EvmResult {
+        self.record_cost(memory_expansion_cost(current_words, new_words))?;
+
+        Ok(())
+    }
+
+    #[must_use]
+    /// Record the cost of `count` Substrate DB reads.
+    /// The ref time of `Runtime::DbWeight` is recorded as gas, and its proof size as an
+    /// external cost. If the handle doesn't meter external costs, the proof size is recorded as
+    /// gas, see `RuntimeHelper::proof_size_gas_cost`.
+    fn record_db_read_cost<Runtime: pallet_evm::Config>(
+        &mut self,
+        count: u64,
+        read_proof_size: u64,
+    ) -> EvmResult
+    where
+        Self: ExternalCostHandle,
+    {
+        let weight = db_reads_weight::<Runtime>(count, read_proof_size);
+
+        self.record_cost(Runtime::GasWeightMapping::weight_to_gas(weight))?;
+
+        match self.record_external_cost(None, Some(weight.proof_size())) {
+            Ok(()) => Ok(()),
+            Err(ExternalCostError::NotImplemented) => {
+                self.record_cost(RuntimeHelper::<Runtime>::proof_size_gas_cost(
+                    weight.proof_size(),
+                ))?;
+
+                Ok(())
+            }
+            Err(ExternalCostError::Exit(exit_status)) => {
+                Err(PrecompileFailure::Error { exit_status })
+            }
+        }
+    }
+
+    #[must_use]
+    /// Refund the proof size of `count` Substrate DB reads recorded with `record_db_read_cost`,
+    /// for example if less reads were done than expected.
+    /// Gas can't be refunded, thus nothing is refunded if the handle doesn't meter external
+    /// costs.
+    fn refund_db_read_cost<Runtime: pallet_evm::Config>(
+        &mut self,
+        count: u64,
+        read_proof_size: u64,
+    ) -> EvmResult
+    where
+        Self: ExternalCostHandle,
+    {
+        let weight = db_reads_weight::<Runtime>(count, read_proof_size);
+
+        match self.refund_external_cost(None, Some(weight.proof_size())) {
+            Ok(()) | Err(ExternalCostError::NotImplemented) => Ok(()),
+            Err(ExternalCostError::Exit(exit_status)) => {
+                Err(PrecompileFailure::Error { exit_status })
+            }
+        }
+    }
+
+    #[must_use]
+    /// Run `f` and return its result along with the gas it consumed, measured as the
+    /// difference of remaining gas before and after it. Only observes the gas, which is
+    /// useful for tests and logging. Errors of `f` are propagated.
+    fn measure_gas<R>(&mut self, f: impl FnOnce(&mut Self) -> EvmResult<R>) -> EvmResult<(R, u64)> {
+        let before = self.remaining_gas();
+        let result = f(self)?;
+        let used_gas = before.saturating_sub(self.remaining_gas());
+
+        Ok((result, used_gas))
+    }
+
     #[must_use]
     /// Check that a function call is compatible with the context it is
     /// called into.
     fn check_function_modifier(&self, modifier: FunctionModifier) -> EvmResult {
+        check_function_modifier(self.context(), self.is_static(), modifier)?;
+
+        Ok(())
+    }
+
+    #[must_use]
+    /// Check that a function call is compatible with the context it is
+    /// called into, returning whether a non-zero value was sent.
+    fn check_function_modifier_value(&self, modifier: FunctionModifier) -> EvmResult<bool> {
         check_function_modifier(self.context(), self.is_static(), modifier)
     }
 
+    #[must_use]
+    /// Check that exactly `expected` value has been sent, for fixed-price payable functions.
+    fn require_value(&self, expected: U256) -> EvmResult {
+        let value = self.context().apparent_value;
+        if value != expected {
+            return Err(revert_with_reason(alloc::format!(
+                "incorrect payment: expected {} got {}",
+                expected,
+                value
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    /// Check that no value has been sent, like for a non-payable function.
+    fn require_no_value(&self) -> EvmResult {
+        if !self.context().apparent_value.is_zero() {
+            return Err(revert_with_reason("function is not payable"));
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    /// Check that the caller is one of the `allowed` addresses.
+    /// The caller is the one of the context (`msg.sender`), which is not necessarily the
+    /// transaction origin. In a DELEGATECALL context it is the caller of the contract that
+    /// delegated the call instead of that contract.
+    fn require_caller(&self, allowed: &[H160]) -> EvmResult {
+        let caller = self.context().caller;
+        if !allowed.contains(&caller) {
+            return Err(revert_with_reason(alloc::format!(
+                "caller not allowed: {:?}",
+                caller
+            )));
+        }
+
+        Ok(())
+    }
+
     #[must_use]
     /// Read the selector from the input data.
     fn read_selector<S>(&self) -> EvmResult<S>
@@ -333,11 +1212,49 @@ impl<T: PrecompileHandle> PrecompileHandleExt for T {
         EvmDataReader::read_selector(self.input())
     }
 
+    #[must_use]
+    /// Read the selector from the input data, following the Solidity conventions for empty
+    /// input (`receive`) and unknown selectors (`fallback`).
+    fn read_selector_or_fallback<S>(&self) -> EvmResult<Selector<S>>
+    where
+        S: num_enum::TryFromPrimitive<Primitive = u32>,
+    {
+        EvmDataReader::read_selector_or_fallback(self.input())
+    }
+
+    #[must_use]
+    /// Peek the selector from the input data, without skipping it.
+    fn peek_selector<S>(&self) -> EvmResult<S>
+    where
+        S: num_enum::TryFromPrimitive<Primitive = u32>,
+    {
+        // Reading the selector doesn't consume the input.
+        EvmDataReader::read_selector(self.input())
+    }
+
     #[must_use]
     /// Returns a reader of the input, skipping the selector.
     fn read_input(&self) -> EvmResult<EvmDataReader> {
         EvmDataReader::new_skip_selector(self.input())
     }
+
+    #[must_use]
+    /// Caller of the precompile (`msg.sender`).
+    fn caller(&self) -> H160 {
+        self.context().caller
+    }
+
+    #[must_use]
+    /// Value sent to the precompile (`msg.value`).
+    fn apparent_value(&self) -> U256 {
+        self.context().apparent_value
+    }
+
+    #[must_use]
+    /// Address of the precompile in the current context (`address(this)`).
+    fn address(&self) -> H160 {
+        self.context().address
+    }
 }
 
 #[must_use]
@@ -348,6 +1265,52 @@ pub fn revert(output: impl AsRef<[u8]>) -> PrecompileFailure {
     }
 }
 
+/// Selector of the Solidity `Error(string)` error, used to encode revert reasons.
+pub const ERROR_STRING_SELECTOR: u32 = 0x08c379a0;
+
+/// Revert with a human-readable reason, encoded as a Solidity `Error(string)`.
+/// Unlike `revert`, which outputs raw bytes, this output can be decoded by Solidity
+/// callers using `try/catch` and by tools such as ethers.js.
+#[must_use]
+pub fn revert_with_reason(reason: impl AsRef<str>) -> PrecompileFailure {
+    revert(encode_revert_reason(reason))
+}
+
+/// Encode a reason as a Solidity `Error(string)`, as used by `revert_with_reason`.
+#[must_use]
+pub fn encode_revert_reason(reason: impl AsRef<str>) -> Vec<u8> {
+    EvmDataWriter::new_with_selector(ERROR_STRING_SELECTOR)
+        .write(Bytes::from(reason.as_ref()))
+        .build()
+}
+
+/// Revert of a call whose selector doesn't match any function, such as "unknown selector
+/// 0xa9059cbb", for the catch-all arm of a precompile. The selector is read from `input`.
+/// Precompiles which should instead succeed without doing anything, like a Solidity contract
+/// with an empty `fallback` function, can use `read_selector_or_fallback` and return
+/// `succeed([])` for `Selector::Fallback`.
+#[must_use]
+pub fn handle_unknown_selector(input: &[u8]) -> PrecompileFailure {
+    let selector: alloc::string::String = input
+        .iter()
+        .take(4)
+        .map(|byte| alloc::format!("{:02x}", byte))
+        .collect();
+
+    revert_with_reason(alloc::format!("unknown selector 0x{}", selector))
+}
+
+/// Revert with a Solidity custom error, such as
+/// `error InsufficientBalance(uint256 available, uint256 required)`, encoded as the selector of
+/// the error followed by its parameters written in `data` (without selector).
+#[must_use]
+pub fn revert_custom_error(selector: [u8; 4], data: EvmDataWriter) -> PrecompileFailure {
+    let mut output = selector.to_vec();
+    output.append(&mut data.build());
+
+    revert(output)
+}
+
 #[must_use]
 pub fn succeed(output: impl AsRef<[u8]>) -> PrecompileOutput {
     PrecompileOutput {
@@ -358,19 +1321,21 @@ pub fn succeed(output: impl AsRef<[u8]>) -> PrecompileOutput {
 
 #[must_use]
 /// Check that a function call is compatible with the context it is
-/// called into.
+/// called into, returning whether a non-zero value was sent.
 fn check_function_modifier(
     context: &Context,
     is_static: bool,
     modifier: FunctionModifier,
-) -> EvmResult {
+) -> EvmResult<bool> {
     if is_static && modifier != FunctionModifier::View {
         return Err(revert("can't call non-static function in static context"));
     }
 
-    if modifier != FunctionModifier::Payable && context.apparent_value > U256::zero() {
+    let value_present = context.apparent_value > U256::zero();
+
+    if modifier != FunctionModifier::Payable && value_present {
         return Err(revert("function is not payable"));
     }
 
-    Ok(())
-}
\ No newline at end of file
+    Ok(value_present)
+}
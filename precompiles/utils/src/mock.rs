@@ -0,0 +1,213 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Mock runtime to test helpers dispatching Substrate calls.
+
+use frame_support::{
+    construct_runtime, parameter_types,
+    traits::Everything,
+    weights::{RuntimeDbWeight, Weight},
+};
+use frame_system::limits::BlockWeights;
+use pallet_evm::{EnsureAddressNever, EnsureAddressRoot, IdentityAddressMapping};
+use sp_core::{H160, H256, U256};
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+};
+
+pub type AccountId = H160;
+pub type Balance = u128;
+pub type BlockNumber = u64;
+pub type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Runtime>;
+pub type Block = frame_system::mocking::MockBlock<Runtime>;
+
+/// Pallet exposing calls with configurable weights and post dispatch info.
+#[frame_support::pallet]
+pub mod pallet_mock_calls {
+    use frame_support::{
+        dispatch::{Pays, PostDispatchInfo},
+        pallet_prelude::*,
+    };
+    use frame_system::pallet_prelude::*;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {}
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Call estimated to `ref_time` and `proof_size`, returning `actual` as its actual
+        /// weight (ref time, proof size).
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(*ref_time, *proof_size))]
+        pub fn weighted(
+            _origin: OriginFor<T>,
+            ref_time: u64,
+            proof_size: u64,
+            actual: Option<(u64, u64)>,
+            pays_fee: bool,
+        ) -> DispatchResultWithPostInfo {
+            Ok(PostDispatchInfo {
+                actual_weight: actual
+                    .map(|(ref_time, proof_size)| Weight::from_parts(ref_time, proof_size)),
+                pays_fee: if pays_fee { Pays::Yes } else { Pays::No },
+            })
+        }
+
+        /// Call estimated to `ref_time` which always fails.
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(*ref_time, 0))]
+        pub fn failing(_origin: OriginFor<T>, ref_time: u64) -> DispatchResult {
+            let _ = ref_time;
+            Err(DispatchError::Other("mock call failed"))
+        }
+
+        /// Call estimated to `ref_time` which is declared to not pay fees.
+        #[pallet::call_index(2)]
+        #[pallet::weight((Weight::from_parts(*ref_time, 0), Pays::No))]
+        pub fn free(_origin: OriginFor<T>, ref_time: u64) -> DispatchResult {
+            let _ = ref_time;
+            Ok(())
+        }
+    }
+}
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+    pub const MockDbWeight: RuntimeDbWeight = RuntimeDbWeight {
+        read: 1_000,
+        write: 2_000,
+    };
+    pub MockBlockWeights: BlockWeights =
+        BlockWeights::simple_max(Weight::from_parts(2_000_000_000_000, 1_000_000));
+}
+
+impl frame_system::Config for Runtime {
+    type BaseCallFilter = Everything;
+    type DbWeight = MockDbWeight;
+    type RuntimeOrigin = RuntimeOrigin;
+    type Index = u64;
+    type BlockNumber = BlockNumber;
+    type RuntimeCall = RuntimeCall;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type BlockWeights = MockBlockWeights;
+    type BlockLength = ();
+    type SS58Prefix = SS58Prefix;
+    type OnSetCode = ();
+    type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+parameter_types! {
+    pub const MinimumPeriod: u64 = 5;
+}
+
+impl pallet_timestamp::Config for Runtime {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = MinimumPeriod;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u128 = 0;
+}
+
+impl pallet_balances::Config for Runtime {
+    type MaxReserves = ();
+    type ReserveIdentifier = ();
+    type MaxLocks = ();
+    type Balance = Balance;
+    type RuntimeEvent = RuntimeEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const PrecompilesValue: () = ();
+    pub const WeightPerGas: Weight = Weight::from_parts(1, 0);
+    // Along with the maximum proof size of `MockBlockWeights`, a byte of proof size costs 10
+    // gas when it is not metered.
+    pub BlockGasLimit: U256 = U256::from(10_000_000u64);
+}
+
+impl pallet_evm::Config for Runtime {
+    type FeeCalculator = ();
+    type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+    type WeightPerGas = WeightPerGas;
+    type CallOrigin = EnsureAddressRoot<AccountId>;
+    type WithdrawOrigin = EnsureAddressNever<AccountId>;
+    type AddressMapping = IdentityAddressMapping;
+    type Currency = Balances;
+    type RuntimeEvent = RuntimeEvent;
+    type Runner = pallet_evm::runner::stack::Runner<Self>;
+    type PrecompilesType = ();
+    type PrecompilesValue = PrecompilesValue;
+    type ChainId = ();
+    type OnChargeTransaction = ();
+    type BlockGasLimit = BlockGasLimit;
+    type BlockHashMapping = pallet_evm::SubstrateBlockHashMapping<Self>;
+    type Timestamp = Timestamp;
+    type FindAuthor = ();
+    type OnCreate = ();
+    type WeightInfo = ();
+}
+
+impl pallet_mock_calls::Config for Runtime {}
+
+construct_runtime!(
+    pub enum Runtime where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system,
+        Balances: pallet_balances,
+        Evm: pallet_evm,
+        Timestamp: pallet_timestamp,
+        MockCalls: pallet_mock_calls,
+    }
+);
+
+pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
+    let t = frame_system::GenesisConfig::default()
+        .build_storage::<Runtime>()
+        .expect("Frame system builds valid default genesis config");
+
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
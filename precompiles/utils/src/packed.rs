@@ -0,0 +1,164 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reader of `abi.encodePacked` data, for interop with contracts using the packed encoding.
+//! Values are tightly packed at their natural width (`address` is 20 bytes, `uint8` is 1 byte,
+//! etc.) without padding nor length prefix, which is why it is separate from `EvmDataReader`.
+//! As a dynamic value has no length prefix, only the last value of an input can be dynamic.
+
+use crate::{Address, Bytes, EvmResult, FixedBytes, RevertReason};
+
+use alloc::{borrow::ToOwned, format};
+use impl_trait_for_tuples::impl_for_tuples;
+use sp_core::{H160, H256, U256};
+
+/// Wrapper around a packed input slice, helping to parse it.
+#[derive(Clone, Debug)]
+pub struct PackedReader<'a> {
+    input: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> PackedReader<'a> {
+    /// Create a new packed input parser.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input, cursor: 0 }
+    }
+
+    /// Read data from the input.
+    pub fn read<T: PackedData>(&mut self) -> EvmResult<T> {
+        T::read(self)
+    }
+
+    /// Amount of bytes remaining after the cursor.
+    pub fn remaining_bytes(&self) -> usize {
+        self.input.len() - self.cursor
+    }
+
+    /// Read all the remaining bytes, which is how the last dynamic value of a packed input is
+    /// encoded.
+    pub fn read_till_end(&mut self) -> &'a [u8] {
+        let data = &self.input[self.cursor..];
+        self.cursor = self.input.len();
+        data
+    }
+
+    /// Check that the whole input has been read.
+    pub fn expect_end(&self) -> EvmResult {
+        match self.remaining_bytes() {
+            0 => Ok(()),
+            len => Err(RevertReason::custom(format!("unexpected trailing {} bytes", len)).into()),
+        }
+    }
+
+    /// Read `len` bytes from the input, reporting the position of the read in case of failure.
+    /// `what` describes the data being read.
+    fn read_slice(&mut self, len: usize, what: &'static str) -> EvmResult<&'a [u8]> {
+        let offset = self.cursor;
+        let data = offset
+            .checked_add(len)
+            .and_then(|end| self.input.get(offset..end))
+            .ok_or_else(|| RevertReason::ReadBeyondInput {
+                what,
+                offset,
+                input_len: self.input.len(),
+            })?;
+
+        self.cursor += len;
+
+        Ok(data)
+    }
+}
+
+/// Data that can be decoded from a packed input.
+pub trait PackedData: Sized {
+    fn read(reader: &mut PackedReader) -> EvmResult<Self>;
+}
+
+#[impl_for_tuples(1, 18)]
+impl PackedData for Tuple {
+    fn read(reader: &mut PackedReader) -> EvmResult<Self> {
+        Ok(for_tuples!( ( #( reader.read::<Tuple>()? ),* ) ))
+    }
+}
+
+impl PackedData for Address {
+    fn read(reader: &mut PackedReader) -> EvmResult<Self> {
+        Ok(H160::from_slice(reader.read_slice(20, "address")?).into())
+    }
+}
+
+impl PackedData for H256 {
+    fn read(reader: &mut PackedReader) -> EvmResult<Self> {
+        Ok(H256::from_slice(reader.read_slice(32, "bytes32")?))
+    }
+}
+
+impl<const N: usize> PackedData for FixedBytes<N> {
+    fn read(reader: &mut PackedReader) -> EvmResult<Self> {
+        let mut buffer = [0u8; N];
+        buffer.copy_from_slice(reader.read_slice(N, "fixed bytes")?);
+        Ok(Self(buffer))
+    }
+}
+
+impl PackedData for U256 {
+    fn read(reader: &mut PackedReader) -> EvmResult<Self> {
+        Ok(U256::from_big_endian(reader.read_slice(32, "uint256")?))
+    }
+}
+
+macro_rules! impl_packeddata_for_uints {
+	($($uint:ty, )*) => {
+		$(
+			impl PackedData for $uint {
+				fn read(reader: &mut PackedReader) -> EvmResult<Self> {
+					let data = reader.read_slice(
+						core::mem::size_of::<Self>(),
+						core::any::type_name::<Self>(),
+					)?;
+
+					let mut buffer = [0u8; core::mem::size_of::<Self>()];
+					buffer.copy_from_slice(data);
+					Ok(Self::from_be_bytes(buffer))
+				}
+			}
+		)*
+	};
+}
+
+impl_packeddata_for_uints!(u8, u16, u32, u64, u128,);
+
+impl PackedData for bool {
+    fn read(reader: &mut PackedReader) -> EvmResult<Self> {
+        match reader.read_slice(1, "bool")? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(RevertReason::custom("invalid bool encoding").into()),
+        }
+    }
+}
+
+/// Dynamic bytes (and strings) are packed without length, and thus take the remaining of the
+/// input. Any value read after it fails.
+impl PackedData for Bytes {
+    fn read(reader: &mut PackedReader) -> EvmResult<Self> {
+        Ok(Self(reader.read_till_end().to_owned()))
+    }
+}
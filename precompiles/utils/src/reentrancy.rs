@@ -0,0 +1,77 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{revert_with_reason, EvmResult, RuntimeHelper};
+
+use alloc::vec::Vec;
+use fp_evm::PrecompileHandle;
+use frame_support::storage::unhashed;
+
+/// Prefix of the storage keys of the guards, followed by the address of the guarded precompile.
+const GUARD_STORAGE_PREFIX: &[u8] = b"precompile-utils:reentrancy-guard:";
+
+/// Guard preventing a precompile from being reentered while it is alive, such as by a
+/// contract the precompile calls which calls the precompile back.
+/// The guard is a flag in the runtime storage which is cleared when the guard is dropped,
+/// which includes early returns with `?` when a subcall reverts.
+/// Other precompiles (with another address) can still be called while the guard is alive.
+///
+/// In static calls the guard only checks the precompile is not already entered, as the flag
+/// can't be written.
+#[must_use]
+pub struct ReentrancyGuard {
+    // Key of the flag to clear on drop, if it was set.
+    key: Option<Vec<u8>>,
+}
+
+impl ReentrancyGuard {
+    /// Enter the precompile executed by `handle`, identified by its code address.
+    /// Reverts with "reentrant call" if a guard of this precompile is already alive.
+    /// Records the cost of a DB read to check the flag, and outside of static calls the cost
+    /// of the 2 DB writes setting and clearing it.
+    pub fn enter<Runtime: pallet_evm::Config>(
+        handle: &mut impl PrecompileHandle,
+    ) -> EvmResult<Self> {
+        let mut key = GUARD_STORAGE_PREFIX.to_vec();
+        key.extend_from_slice(handle.code_address().as_bytes());
+
+        handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+        if unhashed::exists(&key) {
+            return Err(revert_with_reason("reentrant call"));
+        }
+
+        if handle.is_static() {
+            return Ok(Self { key: None });
+        }
+
+        // Clearing the flag is recorded now as the handle is not available on drop.
+        handle.record_cost(RuntimeHelper::<Runtime>::db_write_gas_cost_n(2))?;
+        unhashed::put(&key, &true);
+
+        Ok(Self { key: Some(key) })
+    }
+}
+
+impl Drop for ReentrancyGuard {
+    fn drop(&mut self) {
+        if let Some(key) = &self.key {
+            unhashed::kill(key);
+        }
+    }
+}
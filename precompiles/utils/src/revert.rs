@@ -0,0 +1,172 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{revert_with_reason, EvmDataReader, EvmResult, ERROR_STRING_SELECTOR};
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+use fp_evm::PrecompileFailure;
+
+/// Structured reason of a precompile revert.
+/// Converting it into a `PrecompileFailure` produces a Solidity `Error(string)` revert whose
+/// message is the `Display` output of the reason, which allows tooling and tests to match on
+/// consistent messages.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RevertReason {
+    /// Tried to read data out of the bounds of the input.
+    ReadOutOfBounds { what: &'static str },
+    /// A decoded value doesn't fit in the expected type.
+    ValueIsTooLarge { what: &'static str },
+    /// Tried to read `what` at `offset`, which goes beyond the end of the input.
+    ReadBeyondInput {
+        what: &'static str,
+        offset: usize,
+        input_len: usize,
+    },
+    /// A decoded pointer (offset) targets a location outside of the input.
+    PointerToOutofbound,
+    /// Free-form reason.
+    Custom(String),
+}
+
+impl RevertReason {
+    /// Tried to read `what` out of the bounds of the input.
+    pub fn read_out_of_bounds(what: &'static str) -> Self {
+        Self::ReadOutOfBounds { what }
+    }
+
+    /// Decoded `what` doesn't fit in the expected type.
+    pub fn value_is_too_large(what: &'static str) -> Self {
+        Self::ValueIsTooLarge { what }
+    }
+
+    /// Free-form reason.
+    pub fn custom(reason: impl Into<String>) -> Self {
+        Self::Custom(reason.into())
+    }
+}
+
+impl fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadOutOfBounds { what } => write!(f, "tried to parse {} out of bounds", what),
+            Self::ValueIsTooLarge { what } => write!(f, "{} is too large", what),
+            Self::ReadBeyondInput {
+                what,
+                offset,
+                input_len,
+            } => write!(
+                f,
+                "Reading {} at offset {} would read beyond input (len {})",
+                what, offset, input_len
+            ),
+            Self::PointerToOutofbound => write!(f, "pointer out of bounds"),
+            Self::Custom(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl From<RevertReason> for PrecompileFailure {
+    fn from(reason: RevertReason) -> Self {
+        revert_with_reason(reason.to_string())
+    }
+}
+
+/// Accumulator of revert reasons, allowing to report all the problems of an input at once
+/// instead of only the first one.
+/// Reasons are rendered in the order they were pushed, separated by `; `.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RevertReasons(Vec<RevertReason>);
+
+impl RevertReasons {
+    /// Create an empty accumulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a reason.
+    pub fn push(&mut self, reason: RevertReason) {
+        self.0.push(reason);
+    }
+
+    /// Add a reason, prefixed by the name of the field it is about.
+    pub fn push_field(&mut self, field: &str, reason: RevertReason) {
+        self.0
+            .push(RevertReason::custom(format!("{}: {}", field, reason)));
+    }
+
+    /// Whether no reason has been added.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Revert with all the reasons as a single `Error(string)` if there are any.
+    pub fn check(self) -> EvmResult {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(revert_with_reason(self.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for RevertReasons {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, reason) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", reason)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode the reason of a revert output encoded as a Solidity `Error(string)`.
+/// Returns `None` if the output is not encoded that way.
+pub fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    if EvmDataReader::peek_selector(output).ok()? != ERROR_STRING_SELECTOR {
+        return None;
+    }
+
+    EvmDataReader::new_skip_selector(output).ok()?.read().ok()
+}
+
+/// Prefix the reason of an `Error(string)` revert with the name of the field that was being
+/// decoded. Other failures are returned unchanged.
+pub fn in_field(failure: PrecompileFailure, field: &str) -> PrecompileFailure {
+    match failure {
+        PrecompileFailure::Revert {
+            exit_status,
+            output,
+        } => match decode_revert_reason(&output) {
+            Some(reason) => revert_with_reason(format!("{}: {}", field, reason)),
+            None => PrecompileFailure::Revert {
+                exit_status,
+                output,
+            },
+        },
+        failure => failure,
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2019-2022 PureStake Inc.
+// Copyright 2022      Stake Technologies
+// Copyright 2022      TraceLabs
+// This file is part of Utils package, originally developed by Purestake Inc.
+// Utils package used in NeuroWeb Parachain Network in terms of GPLv3.
+//
+// Utils is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Utils is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Utils.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{memory_expansion_cost, revert, revert_with_reason, EvmData, EvmDataReader, EvmResult};
+
+use alloc::vec::Vec;
+use fp_evm::{Context, ExitReason, PrecompileFailure, PrecompileHandle, Transfer};
+use sp_core::{H160, U256};
+
+/// Call `to` with provided input and value from the precompile.
+/// The subcall is static if the precompile is called statically, in which case transferring
+/// value is not allowed. Like for the EVM `CALL`, at most 63/64 of the remaining gas is
+/// forwarded (EIP-150): a higher gas limit is reduced to it, and it is forwarded if no gas
+/// limit is provided.
+/// The gas used by the subcall is recorded by the handle. Reverts and errors of the subcall
+/// are propagated, with the revert output left intact.
+pub fn call(
+    handle: &mut impl PrecompileHandle,
+    to: H160,
+    input: Vec<u8>,
+    value: U256,
+    gas_limit: Option<u64>,
+) -> EvmResult<(ExitReason, Vec<u8>)> {
+    let caller = handle.context().address;
+    call_from(handle, caller, to, input, value, gas_limit)
+}
+
+/// Call `to` like `call`, but on behalf of `caller`, which is also the source of the value.
+pub fn call_from(
+    handle: &mut impl PrecompileHandle,
+    caller: H160,
+    to: H160,
+    input: Vec<u8>,
+    value: U256,
+    gas_limit: Option<u64>,
+) -> EvmResult<(ExitReason, Vec<u8>)> {
+    call_inner(handle, caller, to, input, value, gas_limit, None)
+}
+
+/// Call `to` like `call_from`, but reverts with "return data too large" if the output of the
+/// subcall (including a revert output) is longer than `max_output_len` bytes.
+/// The memory expansion cost of the output is recorded as if it was copied to memory.
+/// Protects precompiles calling untrusted contracts from handling huge outputs.
+pub fn call_from_bounded(
+    handle: &mut impl PrecompileHandle,
+    caller: H160,
+    to: H160,
+    input: Vec<u8>,
+    value: U256,
+    gas_limit: Option<u64>,
+    max_output_len: usize,
+) -> EvmResult<(ExitReason, Vec<u8>)> {
+    call_inner(
+        handle,
+        caller,
+        to,
+        input,
+        value,
+        gas_limit,
+        Some(max_output_len),
+    )
+}
+
+/// Memory expansion cost of `len` bytes, starting from empty memory.
+fn memory_cost(len: usize) -> u64 {
+    memory_expansion_cost(0, (len as u64).saturating_add(31) / 32)
+}
+
+/// Gas limit forwarded to a subcall when `gas_limit` is requested with `remaining_gas`, which
+/// is at most all but one 64th of the remaining gas (EIP-150).
+fn clamp_gas_limit(remaining_gas: u64, gas_limit: Option<u64>) -> u64 {
+    let max_gas_limit = remaining_gas - remaining_gas / 64;
+
+    gas_limit.map_or(max_gas_limit, |gas_limit| gas_limit.min(max_gas_limit))
+}
+
+fn call_inner(
+    handle: &mut impl PrecompileHandle,
+    caller: H160,
+    to: H160,
+    input: Vec<u8>,
+    value: U256,
+    gas_limit: Option<u64>,
+    max_output_len: Option<usize>,
+) -> EvmResult<(ExitReason, Vec<u8>)> {
+    let is_static = handle.is_static();
+
+    if is_static && !value.is_zero() {
+        return Err(revert("can't transfer value in static context"));
+    }
+
+    let transfer = if value.is_zero() {
+        None
+    } else {
+        Some(Transfer {
+            source: caller,
+            target: to,
+            value,
+        })
+    };
+
+    let context = Context {
+        address: to,
+        caller,
+        apparent_value: value,
+    };
+
+    let gas_limit = clamp_gas_limit(handle.remaining_gas(), gas_limit);
+    let (reason, output) = handle.call(to, transfer, input, Some(gas_limit), is_static, &context);
+
+    if let Some(max_output_len) = max_output_len {
+        if output.len() > max_output_len {
+            return Err(revert_with_reason("return data too large"));
+        }
+
+        handle.record_cost(memory_cost(output.len()))?;
+    }
+
+    match reason {
+        ExitReason::Succeed(_) => Ok((reason, output)),
+        ExitReason::Revert(exit_status) => Err(PrecompileFailure::Revert {
+            exit_status,
+            output,
+        }),
+        ExitReason::Error(exit_status) => Err(PrecompileFailure::Error { exit_status }),
+        ExitReason::Fatal(exit_status) => Err(PrecompileFailure::Fatal { exit_status }),
+    }
+}
+
+/// Decode the output of a subcall, which has no selector, as `T`.
+/// Multiple return values are decoded as a tuple. Reverts if the output is too short, with the
+/// offset of the failed read, or if there are trailing bytes.
+pub fn decode_output<T: EvmData>(output: &[u8]) -> EvmResult<T> {
+    let mut reader = EvmDataReader::new(output);
+    let value = reader.read()?;
+    reader.expect_end()?;
+
+    Ok(value)
+}
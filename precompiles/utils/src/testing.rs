@@ -19,7 +19,8 @@
 use super::*;
 use assert_matches::assert_matches;
 use fp_evm::{
-    ExitReason, ExitSucceed, PrecompileOutput, PrecompileResult, PrecompileSet, Transfer,
+    ExitReason, ExitSucceed, Precompile, PrecompileOutput, PrecompileResult, PrecompileSet,
+    Transfer,
 };
 use sp_std::boxed::Box;
 
@@ -46,6 +47,10 @@ impl<T: FnMut(Subcall) -> SubcallOutput + 'static> SubcallTrait for T {}
 pub type SubcallHandle = Box<dyn SubcallTrait>;
 
 /// Mock handle to write tests for precompiles.
+/// Can be built like `MockHandle::default().gas_limit(100_000).caller(caller).input(input)`.
+/// The `input` and `gas_limit` builders (and `address` and `caller` of `PrecompileHandleExt`)
+/// shadow the getters of the same name, which can still be called through their trait such as
+/// with `PrecompileHandle::input(&handle)`.
 pub struct MockHandle {
     pub gas_limit: u64,
     pub gas_used: u64,
@@ -55,6 +60,9 @@ pub struct MockHandle {
     pub input: Vec<u8>,
     pub context: Context,
     pub is_static: bool,
+    /// Proof size limit, `None` if the proof size is not metered.
+    pub proof_size_limit: Option<u64>,
+    pub proof_size_used: u64,
 }
 
 impl MockHandle {
@@ -68,8 +76,91 @@ impl MockHandle {
             input: Vec::new(),
             context,
             is_static: false,
+            proof_size_limit: None,
+            proof_size_used: 0,
         }
     }
+
+    /// Set the address of the precompile, used both as code address and context address.
+    pub fn address(mut self, address: impl Into<H160>) -> Self {
+        let address = address.into();
+        self.code_address = address;
+        self.context.address = address;
+        self
+    }
+
+    pub fn caller(mut self, caller: impl Into<H160>) -> Self {
+        self.context.caller = caller.into();
+        self
+    }
+
+    pub fn value(mut self, value: impl Into<U256>) -> Self {
+        self.context.apparent_value = value.into();
+        self
+    }
+
+    pub fn gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    pub fn static_call(mut self, is_static: bool) -> Self {
+        self.is_static = is_static;
+        self
+    }
+
+    pub fn input(mut self, input: Vec<u8>) -> Self {
+        self.input = input;
+        self
+    }
+
+    pub fn subcall_handle(mut self, subcall_handle: impl SubcallTrait) -> Self {
+        self.subcall_handle = Some(Box::new(subcall_handle));
+        self
+    }
+
+    /// Meter the proof size as an external cost, up to `proof_size_limit`.
+    /// Otherwise the proof size is not metered and helpers charge it as gas.
+    pub fn proof_size_limit(mut self, proof_size_limit: u64) -> Self {
+        self.proof_size_limit = Some(proof_size_limit);
+        self
+    }
+
+    /// Gas recorded so far.
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    /// Run the precompile `P` with provided input and return its result along with the gas it
+    /// consumed, including the cost of logs, DB accesses and subcalls.
+    /// Allows to snapshot the gas cost of precompiles to catch regressions.
+    pub fn run<P: Precompile>(&mut self, input: Vec<u8>) -> (EvmResult<PrecompileOutput>, u64) {
+        self.input = input;
+
+        let gas_used_before = self.gas_used;
+        let result = P::execute(self);
+
+        (result, self.gas_used - gas_used_before)
+    }
+
+    /// Logs emitted so far.
+    pub fn logs(&self) -> Vec<Log> {
+        self.logs.iter().map(|log| log.0.clone()).collect()
+    }
+}
+
+impl Default for MockHandle {
+    /// Handle for a precompile at the zero address, called by the zero address without value.
+    fn default() -> Self {
+        Self::new(
+            H160::zero(),
+            Context {
+                address: H160::zero(),
+                caller: H160::zero(),
+                apparent_value: U256::zero(),
+            },
+        )
+    }
 }
 
 // Compute the cost of doing a subcall.
@@ -185,17 +276,21 @@ impl PrecompileHandle for MockHandle {
     }
 
     fn record_cost(&mut self, cost: u64) -> Result<(), ExitError> {
-        self.gas_used += cost;
-
-        if self.gas_used > self.gas_limit {
-            Err(ExitError::OutOfGas)
-        } else {
-            Ok(())
+        match self.gas_used.checked_add(cost) {
+            Some(gas_used) if gas_used <= self.gas_limit => {
+                self.gas_used = gas_used;
+                Ok(())
+            }
+            // Like the EVM gasometer, running out of gas consumes all the gas.
+            _ => {
+                self.gas_used = self.gas_limit;
+                Err(ExitError::OutOfGas)
+            }
         }
     }
 
     fn remaining_gas(&self) -> u64 {
-        self.gas_limit - self.gas_used
+        self.gas_limit.saturating_sub(self.gas_used)
     }
 
     fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
@@ -233,6 +328,162 @@ impl PrecompileHandle for MockHandle {
     }
 }
 
+impl ExternalCostHandle for MockHandle {
+    fn record_external_cost(
+        &mut self,
+        _ref_time: Option<u64>,
+        proof_size: Option<u64>,
+    ) -> Result<(), ExternalCostError> {
+        let limit = self
+            .proof_size_limit
+            .ok_or(ExternalCostError::NotImplemented)?;
+
+        let used = self
+            .proof_size_used
+            .saturating_add(proof_size.unwrap_or_default());
+
+        if used > limit {
+            return Err(ExternalCostError::Exit(ExitError::OutOfGas));
+        }
+
+        self.proof_size_used = used;
+        Ok(())
+    }
+
+    fn refund_external_cost(
+        &mut self,
+        _ref_time: Option<u64>,
+        proof_size: Option<u64>,
+    ) -> Result<(), ExternalCostError> {
+        if self.proof_size_limit.is_none() {
+            return Err(ExternalCostError::NotImplemented);
+        }
+
+        self.proof_size_used = self
+            .proof_size_used
+            .saturating_sub(proof_size.unwrap_or_default());
+        Ok(())
+    }
+
+    fn remaining_proof_size(&self) -> Option<u64> {
+        self.proof_size_limit
+            .map(|limit| limit.saturating_sub(self.proof_size_used))
+    }
+}
+
+/// Handle recording the costs of a precompile without doing any EVM work, to calibrate the
+/// gas costs of precompiles (such as the weight to gas mapping) in benchmarks.
+/// Every recorded cost is kept, and subcalls return a fixed result without costing any gas.
+pub struct CalibrationHandle {
+    pub code_address: H160,
+    pub input: Vec<u8>,
+    pub context: Context,
+    pub is_static: bool,
+    pub gas_limit: u64,
+    /// Costs recorded so far, in order.
+    pub costs: Vec<u64>,
+    /// Amount of subcalls done so far.
+    pub subcalls: usize,
+    /// Amount of logs emitted so far.
+    pub logs: usize,
+    pub subcall_result: (ExitReason, Vec<u8>),
+}
+
+impl CalibrationHandle {
+    pub fn new(code_address: H160, context: Context) -> Self {
+        Self {
+            code_address,
+            input: Vec::new(),
+            context,
+            is_static: false,
+            gas_limit: u64::MAX,
+            costs: vec![],
+            subcalls: 0,
+            logs: 0,
+            subcall_result: (ExitReason::Succeed(ExitSucceed::Returned), vec![]),
+        }
+    }
+
+    pub fn with_input(mut self, input: Vec<u8>) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Set the result returned by every subcall.
+    pub fn with_subcall_result(mut self, reason: ExitReason, output: Vec<u8>) -> Self {
+        self.subcall_result = (reason, output);
+        self
+    }
+
+    /// Amount of `record_cost` calls so far.
+    pub fn record_count(&self) -> usize {
+        self.costs.len()
+    }
+
+    /// Sum of the costs recorded so far.
+    pub fn total_cost(&self) -> u64 {
+        self.costs
+            .iter()
+            .fold(0u64, |total, cost| total.saturating_add(*cost))
+    }
+}
+
+impl PrecompileHandle for CalibrationHandle {
+    fn call(
+        &mut self,
+        _address: H160,
+        _transfer: Option<Transfer>,
+        _input: Vec<u8>,
+        _target_gas: Option<u64>,
+        _is_static: bool,
+        _context: &Context,
+    ) -> (ExitReason, Vec<u8>) {
+        self.subcalls += 1;
+        self.subcall_result.clone()
+    }
+
+    fn record_cost(&mut self, cost: u64) -> Result<(), ExitError> {
+        if cost > self.remaining_gas() {
+            return Err(ExitError::OutOfGas);
+        }
+
+        self.costs.push(cost);
+        Ok(())
+    }
+
+    fn remaining_gas(&self) -> u64 {
+        self.gas_limit.saturating_sub(self.total_cost())
+    }
+
+    fn log(&mut self, _address: H160, _topics: Vec<H256>, _data: Vec<u8>) -> Result<(), ExitError> {
+        self.logs += 1;
+        Ok(())
+    }
+
+    fn code_address(&self) -> H160 {
+        self.code_address
+    }
+
+    fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    fn context(&self) -> &Context {
+        &self.context
+    }
+
+    fn is_static(&self) -> bool {
+        self.is_static
+    }
+
+    fn gas_limit(&self) -> Option<u64> {
+        Some(self.gas_limit)
+    }
+}
+
+// External costs are charged as gas, which keeps them in the recorded costs.
+impl ExternalCostHandle for CalibrationHandle {}
+
 pub struct PrecompilesTester<'p, P> {
     precompiles: &'p P,
     handle: MockHandle,
@@ -408,6 +659,12 @@ impl<T: PrecompileSet> PrecompileTesterExt for T {
 #[derive(Clone, PartialEq, Eq)]
 pub struct PrettyLog(Log);
 
+impl From<Log> for PrettyLog {
+    fn from(log: Log) -> Self {
+        Self(log)
+    }
+}
+
 impl core::fmt::Debug for PrettyLog {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
         let bytes = self
@@ -427,4 +684,117 @@ impl core::fmt::Debug for PrettyLog {
             .field("data_utf8", &message)
             .finish()
     }
+}
+
+/// Check that the result is a revert with provided reason.
+/// `Error(string)` reverts are decoded and their message is compared to the reason, while
+/// other reverts have their raw output compared to it.
+#[track_caller]
+pub fn assert_revert_eq<T: core::fmt::Debug>(
+    result: Result<T, PrecompileFailure>,
+    reason: impl AsRef<[u8]>,
+) {
+    let reason = reason.as_ref();
+
+    match result {
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            match revert::decode_revert_reason(&output) {
+                Some(decoded) => similar_asserts::assert_eq!(
+                    decoded.as_str(),
+                    String::from_utf8_lossy(reason).as_ref()
+                ),
+                None => assert!(
+                    output == reason,
+                    "expected raw revert output {:?}, got {:?}",
+                    String::from_utf8_lossy(reason),
+                    String::from_utf8_lossy(&output),
+                ),
+            }
+        }
+        other => panic!("expected a revert, got {:?}", other),
+    }
+}
+
+/// Check that the precompile `P` rejects provided input when called from a `STATICCALL`,
+/// as `check_function_modifier` does for functions that are not views.
+#[track_caller]
+pub fn assert_static_rejected<P: Precompile>(input: Vec<u8>) {
+    let (result, _) = MockHandle::default().static_call(true).run::<P>(input);
+
+    assert_revert_eq(result, "can't call non-static function in static context");
+}
+
+/// Check that provided log has been recorded by the handle.
+#[track_caller]
+pub fn assert_event(handle: &MockHandle, expected: Log) {
+    let expected = PrettyLog(expected);
+
+    assert!(
+        handle.logs.contains(&expected),
+        "expected log {:?} to be recorded, got {:?}",
+        expected,
+        handle.logs,
+    );
+}
+
+/// Conversion of `EvmData` values from and to `ethabi` tokens, allowing to check the encoding
+/// of this crate against `ethabi` as a reference implementation.
+pub trait EthabiToken: Sized {
+    fn to_token(self) -> ethabi::Token;
+    /// Returns `None` if the token is not of the matching kind.
+    fn from_token(token: ethabi::Token) -> Option<Self>;
+}
+
+impl EthabiToken for U256 {
+    fn to_token(self) -> ethabi::Token {
+        let mut buffer = [0u8; 32];
+        self.to_big_endian(&mut buffer);
+        ethabi::Token::Uint(ethabi::Uint::from_big_endian(&buffer))
+    }
+
+    fn from_token(token: ethabi::Token) -> Option<Self> {
+        let mut buffer = [0u8; 32];
+        token.into_uint()?.to_big_endian(&mut buffer);
+        Some(U256::from_big_endian(&buffer))
+    }
+}
+
+impl EthabiToken for H160 {
+    fn to_token(self) -> ethabi::Token {
+        ethabi::Token::Address(ethabi::Address::from_slice(self.as_bytes()))
+    }
+
+    fn from_token(token: ethabi::Token) -> Option<Self> {
+        Some(H160::from_slice(token.into_address()?.as_bytes()))
+    }
+}
+
+impl EthabiToken for Address {
+    fn to_token(self) -> ethabi::Token {
+        self.0.to_token()
+    }
+
+    fn from_token(token: ethabi::Token) -> Option<Self> {
+        H160::from_token(token).map(Address)
+    }
+}
+
+impl EthabiToken for Bytes {
+    fn to_token(self) -> ethabi::Token {
+        ethabi::Token::Bytes(self.0)
+    }
+
+    fn from_token(token: ethabi::Token) -> Option<Self> {
+        token.into_bytes().map(Bytes)
+    }
+}
+
+impl<T: EthabiToken> EthabiToken for Vec<T> {
+    fn to_token(self) -> ethabi::Token {
+        ethabi::Token::Array(self.into_iter().map(T::to_token).collect())
+    }
+
+    fn from_token(token: ethabi::Token) -> Option<Self> {
+        token.into_array()?.into_iter().map(T::from_token).collect()
+    }
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
 use super::*;
+use fp_evm::{ExitReason, Precompile};
+use frame_support::dispatch::Pays;
 use hex_literal::hex;
 use sp_core::{H256, U256};
 
@@ -349,7 +351,12 @@ fn read_address_array_size_too_big() {
     match reader.read::<Vec<Address>>() {
         Ok(_) => panic!("should not parse correctly"),
         Err(PrecompileFailure::Revert { output: err, .. }) => {
-            assert_eq!(err, b"tried to parse H160 out of bounds")
+            assert_eq!(
+                err,
+                encode_revert_reason(
+                    "Reading H160 at offset 224 would read beyond input (len 224)"
+                )
+            )
         }
         Err(_) => panic!("unexpected error"),
     }
@@ -721,4 +728,4604 @@ fn read_complex_solidity_function() {
 
     // weight
     assert_eq!(reader.read::<U256>().unwrap(), 100u32.into());
-}
\ No newline at end of file
+}
+
+#[test]
+fn revert_with_reason_matches_solidity_encoding() {
+    // Output of `require(false, "Not enough Ether provided.")` given by the Solidity docs.
+    let expected = hex!(
+        "08c379a0
+		0000000000000000000000000000000000000000000000000000000000000020
+		000000000000000000000000000000000000000000000000000000000000001a
+		4e6f7420656e6f7567682045746865722070726f76696465642e000000000000"
+    );
+
+    match revert_with_reason("Not enough Ether provided.") {
+        PrecompileFailure::Revert { output, .. } => assert_eq!(output, expected),
+        _ => panic!("expected a revert"),
+    }
+}
+
+#[test]
+fn revert_with_reason_round_trips() {
+    let reason = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod";
+
+    let output = match revert_with_reason(reason) {
+        PrecompileFailure::Revert { output, .. } => output,
+        _ => panic!("expected a revert"),
+    };
+
+    assert_eq!(&output[0..4], &ERROR_STRING_SELECTOR.to_be_bytes());
+
+    let mut reader = EvmDataReader::new_skip_selector(&output).expect("to skip selector");
+    let parsed: Bytes = reader.read().expect("to correctly parse reason");
+
+    assert_eq!(parsed.as_str().expect("valid utf8"), reason);
+}
+
+#[test]
+fn revert_keeps_raw_output() {
+    match revert(&[0xde, 0xad, 0xbe, 0xef]) {
+        PrecompileFailure::Revert { output, .. } => {
+            assert_eq!(output, vec![0xde, 0xad, 0xbe, 0xef])
+        }
+        _ => panic!("expected a revert"),
+    }
+}
+
+#[test]
+fn revert_reason_display() {
+    assert_eq!(
+        RevertReason::read_out_of_bounds("U256").to_string(),
+        "tried to parse U256 out of bounds"
+    );
+    assert_eq!(
+        RevertReason::value_is_too_large("array length").to_string(),
+        "array length is too large"
+    );
+    assert_eq!(
+        RevertReason::PointerToOutofbound.to_string(),
+        "pointer out of bounds"
+    );
+    assert_eq!(RevertReason::custom("foo").to_string(), "foo");
+}
+
+#[test]
+fn revert_reason_is_error_string_encoded() {
+    let reason = RevertReason::read_out_of_bounds("U256");
+
+    assert_eq!(
+        PrecompileFailure::from(reason.clone()),
+        revert_with_reason(reason.to_string())
+    );
+}
+
+#[test]
+fn reader_failures_use_revert_reason() {
+    let mut reader = EvmDataReader::new(&[0u8; 12]);
+
+    match reader.read::<U256>() {
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            assert_eq!(
+                output,
+                encode_revert_reason("Reading U256 at offset 0 would read beyond input (len 12)")
+            )
+        }
+        _ => panic!("expected a revert"),
+    }
+}
+
+macro_rules! test_int_round_trip {
+    ($($test:ident: $int:ty,)*) => {
+        $(
+            #[test]
+            fn $test() {
+                for value in [<$int>::MIN, <$int>::MIN + 1, -1, 0, 1, <$int>::MAX - 1, <$int>::MAX] {
+                    let writer_output = EvmDataWriter::new().write(value).build();
+                    assert_eq!(writer_output.len(), 32);
+
+                    let mut reader = EvmDataReader::new(&writer_output);
+                    let parsed: $int = reader.read().expect("to correctly parse int");
+                    assert_eq!(value, parsed);
+
+                    let parsed: I256 = EvmDataReader::new(&writer_output)
+                        .read()
+                        .expect("to correctly parse I256");
+                    assert_eq!(parsed, I256::from(value));
+                }
+            }
+        )*
+    };
+}
+
+test_int_round_trip!(
+    int_round_trip_i8: i8,
+    int_round_trip_i16: i16,
+    int_round_trip_i32: i32,
+    int_round_trip_i64: i64,
+    int_round_trip_i128: i128,
+);
+
+#[test]
+fn write_minus_one_is_all_ones() {
+    let writer_output = EvmDataWriter::new().write(-1i8).build();
+    assert_eq!(writer_output, [0xffu8; 32]);
+
+    let writer_output = EvmDataWriter::new().write(I256::from(-1i128)).build();
+    assert_eq!(writer_output, [0xffu8; 32]);
+}
+
+#[test]
+fn write_i128_min_is_sign_extended() {
+    let writer_output = EvmDataWriter::new().write(i128::MIN).build();
+
+    let mut expected_output = [0xffu8; 32];
+    expected_output[16..].copy_from_slice(&i128::MIN.to_be_bytes());
+
+    assert_eq!(writer_output, expected_output);
+}
+
+#[test]
+fn read_int_out_of_range() {
+    // 128 doesn't fit in an i8.
+    let writer_output = EvmDataWriter::new().write(128i16).build();
+    match EvmDataReader::new(&writer_output).read::<i8>() {
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            assert_eq!(output, encode_revert_reason("value out of range for int8"))
+        }
+        _ => panic!("expected a revert"),
+    }
+
+    // -129 doesn't fit in an i8.
+    let writer_output = EvmDataWriter::new().write(-129i16).build();
+    assert!(EvmDataReader::new(&writer_output).read::<i8>().is_err());
+
+    // i128::MIN doesn't fit in an i64.
+    let writer_output = EvmDataWriter::new().write(i128::MIN).build();
+    match EvmDataReader::new(&writer_output).read::<i64>() {
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            assert_eq!(output, encode_revert_reason("value out of range for int64"))
+        }
+        _ => panic!("expected a revert"),
+    }
+
+    // A positive value with the sign bit of the target width set doesn't fit.
+    let writer_output = EvmDataWriter::new().write(u128::MAX).build();
+    assert!(EvmDataReader::new(&writer_output).read::<i128>().is_err());
+}
+
+#[test]
+fn i256_bounds() {
+    assert!(I256::MIN.is_negative());
+    assert!(!I256::MAX.is_negative());
+    assert!(!I256::zero().is_negative());
+
+    // |-2^255| = 2^255, which has the same bit pattern as int256::MIN.
+    assert_eq!(I256::MIN.unsigned_abs(), I256::MIN.0);
+    assert_eq!(I256::MAX.unsigned_abs(), I256::MAX.0);
+    assert_eq!(I256::from(-42i64).unsigned_abs(), U256::from(42));
+
+    for value in [I256::MIN, I256::MAX] {
+        let writer_output = EvmDataWriter::new().write(value).build();
+        let parsed: I256 = EvmDataReader::new(&writer_output)
+            .read()
+            .expect("to correctly parse I256");
+        assert_eq!(value, parsed);
+    }
+
+    assert!(i128::try_from(I256::MIN).is_err());
+    assert_eq!(i128::try_from(I256::from(i128::MIN)), Ok(i128::MIN));
+}
+
+#[test]
+fn balance_delta_apply_to() {
+    let balance = U256::from(1_000u32);
+
+    assert_eq!(
+        BalanceDelta(I256::from(250i64)).apply_to(balance),
+        Ok(U256::from(1_250u32))
+    );
+    assert_eq!(
+        BalanceDelta(I256::from(-250i64)).apply_to(balance),
+        Ok(U256::from(750u32))
+    );
+    assert_eq!(
+        BalanceDelta(I256::from(-1_000i64)).apply_to(balance),
+        Ok(U256::zero())
+    );
+    assert_eq!(
+        BalanceDelta(I256::from(-1_001i64)).apply_to(balance),
+        Err(revert_with_reason("insufficient balance"))
+    );
+    assert_eq!(
+        BalanceDelta(I256::MIN).apply_to(U256::MAX),
+        Ok(U256::MAX - I256::MIN.0)
+    );
+    assert_eq!(
+        BalanceDelta(I256::from(1i64)).apply_to(U256::MAX),
+        Err(revert_with_reason("balance overflow"))
+    );
+
+    let delta = BalanceDelta(I256::from(-42i64));
+    let output = EvmDataWriter::new().write(delta).build();
+    assert_eq!(
+        output,
+        EvmDataWriter::new().write(I256::from(-42i64)).build()
+    );
+    assert_eq!(
+        EvmDataReader::new(&output).read::<BalanceDelta>(),
+        Ok(delta)
+    );
+}
+
+#[test]
+fn read_u8_too_large() {
+    let writer_output = EvmDataWriter::new().write(U256::from(0x0100)).build();
+
+    match EvmDataReader::new(&writer_output).read::<u8>() {
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            assert_eq!(output, encode_revert_reason("value too large for uint8"))
+        }
+        _ => panic!("expected a revert"),
+    }
+}
+
+#[test]
+fn read_uints_too_large() {
+    let writer_output = EvmDataWriter::new().write(U256::from(u16::MAX) + 1).build();
+    assert!(EvmDataReader::new(&writer_output).read::<u16>().is_err());
+
+    let writer_output = EvmDataWriter::new().write(U256::from(u32::MAX) + 1).build();
+    assert!(EvmDataReader::new(&writer_output).read::<u32>().is_err());
+
+    let writer_output = EvmDataWriter::new().write(U256::from(u64::MAX) + 1).build();
+    assert!(EvmDataReader::new(&writer_output).read::<u64>().is_err());
+
+    let writer_output = EvmDataWriter::new()
+        .write(U256::from(u128::MAX) + 1)
+        .build();
+    match EvmDataReader::new(&writer_output).read::<u128>() {
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            assert_eq!(output, encode_revert_reason("value too large for uint128"))
+        }
+        _ => panic!("expected a revert"),
+    }
+}
+
+#[test]
+fn u64_max_round_trips() {
+    let writer_output = EvmDataWriter::new().write(u64::MAX).build();
+
+    let mut expected_output = [0u8; 32];
+    expected_output[24..].copy_from_slice(&[0xff; 8]);
+    assert_eq!(writer_output, expected_output);
+
+    let parsed: u64 = EvmDataReader::new(&writer_output)
+        .read()
+        .expect("to correctly parse u64");
+    assert_eq!(parsed, u64::MAX);
+}
+
+#[test]
+fn read_u8_max() {
+    let writer_output = EvmDataWriter::new().write(u8::MAX).build();
+
+    let parsed: u8 = EvmDataReader::new(&writer_output)
+        .read()
+        .expect("to correctly parse u8");
+    assert_eq!(parsed, u8::MAX);
+}
+
+// ABI encoding of `uint256[]` `[1, 2, 3]`, as produced by ethers.js.
+const ETHERS_U256_ARRAY: [u8; 0xa0] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000000020
+	0000000000000000000000000000000000000000000000000000000000000003
+	0000000000000000000000000000000000000000000000000000000000000001
+	0000000000000000000000000000000000000000000000000000000000000002
+	0000000000000000000000000000000000000000000000000000000000000003"
+);
+
+// ABI encoding of `uint256[]` `[]`, as produced by ethers.js.
+const ETHERS_EMPTY_U256_ARRAY: [u8; 0x40] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000000020
+	0000000000000000000000000000000000000000000000000000000000000000"
+);
+
+// ABI encoding of `uint256[][]` `[[1, 2], [3]]`, as produced by ethers.js.
+const ETHERS_NESTED_U256_ARRAY: [u8; 0x120] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000000020
+	0000000000000000000000000000000000000000000000000000000000000002
+	0000000000000000000000000000000000000000000000000000000000000040
+	00000000000000000000000000000000000000000000000000000000000000a0
+	0000000000000000000000000000000000000000000000000000000000000002
+	0000000000000000000000000000000000000000000000000000000000000001
+	0000000000000000000000000000000000000000000000000000000000000002
+	0000000000000000000000000000000000000000000000000000000000000001
+	0000000000000000000000000000000000000000000000000000000000000003"
+);
+
+// ABI encoding of `uint256[][]` `[[], [1]]`, as produced by ethers.js.
+const ETHERS_NESTED_WITH_EMPTY_U256_ARRAY: [u8; 0xe0] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000000020
+	0000000000000000000000000000000000000000000000000000000000000002
+	0000000000000000000000000000000000000000000000000000000000000040
+	0000000000000000000000000000000000000000000000000000000000000060
+	0000000000000000000000000000000000000000000000000000000000000000
+	0000000000000000000000000000000000000000000000000000000000000001
+	0000000000000000000000000000000000000000000000000000000000000001"
+);
+
+#[test]
+fn u256_array_matches_ethers() {
+    let array: Vec<U256> = vec![1.into(), 2.into(), 3.into()];
+
+    let writer_output = EvmDataWriter::new().write(array.clone()).build();
+    assert_eq!(writer_output, ETHERS_U256_ARRAY);
+
+    let parsed: Vec<U256> = EvmDataReader::new(&ETHERS_U256_ARRAY)
+        .read()
+        .expect("to correctly parse Vec<U256>");
+    assert_eq!(parsed, array);
+}
+
+#[test]
+fn empty_array_matches_ethers() {
+    let writer_output = EvmDataWriter::new().write(Vec::<U256>::new()).build();
+    assert_eq!(writer_output, ETHERS_EMPTY_U256_ARRAY);
+
+    let parsed: Vec<U256> = EvmDataReader::new(&ETHERS_EMPTY_U256_ARRAY)
+        .read()
+        .expect("to correctly parse Vec<U256>");
+    assert!(parsed.is_empty());
+}
+
+#[test]
+fn nested_u256_array_matches_ethers() {
+    let array: Vec<Vec<U256>> = vec![vec![1.into(), 2.into()], vec![3.into()]];
+
+    let writer_output = EvmDataWriter::new().write(array.clone()).build();
+    assert_eq!(writer_output, ETHERS_NESTED_U256_ARRAY);
+
+    let parsed: Vec<Vec<U256>> = EvmDataReader::new(&ETHERS_NESTED_U256_ARRAY)
+        .read()
+        .expect("to correctly parse Vec<Vec<U256>>");
+    assert_eq!(parsed, array);
+
+    let array: Vec<Vec<U256>> = vec![vec![], vec![1.into()]];
+
+    let writer_output = EvmDataWriter::new().write(array.clone()).build();
+    assert_eq!(writer_output, ETHERS_NESTED_WITH_EMPTY_U256_ARRAY);
+
+    let parsed: Vec<Vec<U256>> = EvmDataReader::new(&ETHERS_NESTED_WITH_EMPTY_U256_ARRAY)
+        .read()
+        .expect("to correctly parse Vec<Vec<U256>>");
+    assert_eq!(parsed, array);
+}
+
+#[test]
+fn read_u256_array_length_exceeds_input() {
+    let mut input = ETHERS_U256_ARRAY;
+    // Claim a huge amount of items while only 3 are provided.
+    U256::from(u32::MAX).to_big_endian(&mut input[0x20..0x40]);
+
+    match EvmDataReader::new(&input).read::<Vec<U256>>() {
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            assert_eq!(
+                output,
+                encode_revert_reason(
+                    "Reading U256 at offset 160 would read beyond input (len 160)"
+                )
+            )
+        }
+        _ => panic!("expected a revert"),
+    }
+}
+
+#[test]
+fn fixed_address_array_is_inline() {
+    let array = [
+        Address(H160::repeat_byte(0x11)),
+        Address(H160::repeat_byte(0x22)),
+    ];
+
+    let writer_output = EvmDataWriter::new().write(array).build();
+    assert_eq!(writer_output.len(), 0x40);
+
+    let mut reader = EvmDataReader::new(&writer_output);
+    assert_eq!(reader.read::<Address>().expect("read 1st"), array[0]);
+    assert_eq!(reader.read::<Address>().expect("read 2nd"), array[1]);
+
+    let parsed: [Address; 2] = EvmDataReader::new(&writer_output)
+        .read()
+        .expect("to correctly parse [Address; 2]");
+    assert_eq!(parsed, array);
+}
+
+#[test]
+fn fixed_u256_array_round_trips() {
+    let array: [U256; 4] = [1.into(), 2.into(), 3.into(), U256::MAX];
+
+    let writer_output = EvmDataWriter::new()
+        .write(array)
+        .write(U256::from(42))
+        .build();
+    assert_eq!(writer_output.len(), 0xa0);
+
+    let mut reader = EvmDataReader::new(&writer_output);
+    let parsed: [U256; 4] = reader.read().expect("to correctly parse [U256; 4]");
+    assert_eq!(parsed, array);
+    assert_eq!(
+        reader.read::<U256>().expect("read following value"),
+        42.into()
+    );
+}
+
+#[test]
+fn fixed_array_of_dynamic_type_is_dynamic() {
+    assert!(<[U256; 2]>::has_static_size());
+    assert!(!<[Bytes; 2]>::has_static_size());
+
+    // ABI encoding of `bytes[2]` `["0x01", "0x0203"]`.
+    let expected = hex!(
+        "0000000000000000000000000000000000000000000000000000000000000020
+		0000000000000000000000000000000000000000000000000000000000000040
+		0000000000000000000000000000000000000000000000000000000000000080
+		0000000000000000000000000000000000000000000000000000000000000001
+		0100000000000000000000000000000000000000000000000000000000000000
+		0000000000000000000000000000000000000000000000000000000000000002
+		0203000000000000000000000000000000000000000000000000000000000000"
+    );
+
+    let array = [Bytes::from(&[1u8][..]), Bytes::from(&[2u8, 3][..])];
+
+    let writer_output = EvmDataWriter::new().write(array.clone()).build();
+    assert_eq!(writer_output, expected);
+
+    let parsed: [Bytes; 2] = EvmDataReader::new(&expected)
+        .read()
+        .expect("to correctly parse [Bytes; 2]");
+    assert_eq!(parsed, array);
+}
+
+#[test]
+fn mixed_tuple_matches_ethers() {
+    // ABI encoding of `tuple(uint256,bytes,address)` `[1, "0x1234", "0x1111..11"]` by ethers.js.
+    let expected = hex!(
+        "0000000000000000000000000000000000000000000000000000000000000020
+		0000000000000000000000000000000000000000000000000000000000000001
+		0000000000000000000000000000000000000000000000000000000000000060
+		0000000000000000000000001111111111111111111111111111111111111111
+		0000000000000000000000000000000000000000000000000000000000000002
+		1234000000000000000000000000000000000000000000000000000000000000"
+    );
+
+    let value = (
+        U256::one(),
+        Bytes::from(&hex!("1234")[..]),
+        Address(H160::repeat_byte(0x11)),
+    );
+
+    assert!(!<(U256, Bytes, Address)>::has_static_size());
+    assert!(<(U256, bool, Address)>::has_static_size());
+
+    let writer_output = EvmDataWriter::new().write(value.clone()).build();
+    assert_eq!(writer_output, expected);
+
+    let parsed: (U256, Bytes, Address) = EvmDataReader::new(&expected)
+        .read()
+        .expect("to correctly parse tuple");
+    assert_eq!(parsed, value);
+}
+
+#[test]
+fn static_tuple_is_inline() {
+    let value = (U256::from(42), Address(H160::repeat_byte(0x11)), true);
+
+    let writer_output = EvmDataWriter::new().write(value).build();
+    assert_eq!(writer_output.len(), 0x60);
+
+    let mut reader = EvmDataReader::new(&writer_output);
+    assert_eq!(reader.read::<U256>().expect("read 1st"), value.0);
+    assert_eq!(reader.read::<Address>().expect("read 2nd"), value.1);
+    assert_eq!(reader.read::<bool>().expect("read 3rd"), value.2);
+
+    let parsed: (U256, Address, bool) = EvmDataReader::new(&writer_output)
+        .read()
+        .expect("to correctly parse tuple");
+    assert_eq!(parsed, value);
+}
+
+#[test]
+fn string_with_multibyte_characters_round_trips() {
+    let value = String::from("Zdravo, svijete! ✓ ünïcödé");
+
+    let writer_output = EvmDataWriter::new().write(value.clone()).build();
+    assert_eq!(
+        writer_output,
+        EvmDataWriter::new()
+            .write(Bytes::from(value.as_str()))
+            .build()
+    );
+
+    let parsed: String = EvmDataReader::new(&writer_output)
+        .read()
+        .expect("to correctly parse String");
+    assert_eq!(parsed, value);
+}
+
+#[test]
+fn short_string_is_padded() {
+    let writer_output = EvmDataWriter::new().write(String::from("abc")).build();
+
+    let mut expected = [0u8; 0x60];
+    expected[0x1f] = 0x20;
+    expected[0x3f] = 3;
+    expected[0x40..0x43].copy_from_slice(b"abc");
+
+    assert_eq!(writer_output, expected);
+}
+
+#[test]
+fn empty_string_has_no_data_word() {
+    let writer_output = EvmDataWriter::new().write(String::new()).build();
+
+    let mut expected = [0u8; 0x40];
+    expected[0x1f] = 0x20;
+
+    assert_eq!(writer_output, expected);
+
+    let parsed: String = EvmDataReader::new(&writer_output)
+        .read()
+        .expect("to correctly parse String");
+    assert!(parsed.is_empty());
+}
+
+#[test]
+fn read_string_with_truncated_sequence() {
+    // First 2 bytes of the 3 bytes encoding of '✓'.
+    let writer_output = EvmDataWriter::new()
+        .write(Bytes::from(&[b'a', 0xe2, 0x9c][..]))
+        .build();
+
+    match EvmDataReader::new(&writer_output).read::<String>() {
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            assert_eq!(output, encode_revert_reason("invalid UTF-8 in string"))
+        }
+        _ => panic!("expected a revert"),
+    }
+}
+
+#[test]
+fn bounded_bytes_rejects_too_long() {
+    use frame_support::traits::ConstU32;
+
+    let writer_output = EvmDataWriter::new().write(Bytes(vec![0xaa; 17])).build();
+
+    match EvmDataReader::new(&writer_output).read::<BoundedBytes<ConstU32<16>>>() {
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            assert_eq!(output, encode_revert_reason("bytes too long"))
+        }
+        _ => panic!("expected a revert"),
+    }
+
+    assert!(BoundedBytes::<ConstU32<16>>::try_from(vec![0xaa; 17]).is_err());
+}
+
+#[test]
+fn bounded_bytes_round_trips() {
+    use frame_support::traits::ConstU32;
+
+    let value = BoundedBytes::<ConstU32<16>>::try_from(vec![0xaa; 16]).expect("fits in bound");
+
+    let writer_output = EvmDataWriter::new().write(value.clone()).build();
+    assert_eq!(
+        writer_output,
+        EvmDataWriter::new().write(Bytes(vec![0xaa; 16])).build()
+    );
+
+    let parsed: BoundedBytes<ConstU32<16>> = EvmDataReader::new(&writer_output)
+        .read()
+        .expect("to correctly parse BoundedBytes");
+    assert_eq!(parsed, value);
+    assert_eq!(&parsed[..], &[0xaa; 16][..]);
+    assert_eq!(Vec::<u8>::from(parsed), vec![0xaa; 16]);
+}
+
+#[precompile_utils_macro::generate_function_selector]
+#[derive(Debug, PartialEq)]
+enum PeekAction {
+    Transfer = "transfer(address,uint256)",
+}
+
+#[test]
+fn peek_then_read_selector() {
+    let input = EvmDataWriter::new_with_selector(PeekAction::Transfer)
+        .write(Address(H160::repeat_byte(0x11)))
+        .write(U256::from(42))
+        .build();
+
+    assert_eq!(
+        EvmDataReader::peek_selector(&input).expect("there is a selector"),
+        0xa9059cbb
+    );
+
+    let mut handle = testing::MockHandle::new(
+        H160::repeat_byte(0xff),
+        Context {
+            address: H160::repeat_byte(0xff),
+            caller: H160::repeat_byte(0xaa),
+            apparent_value: U256::zero(),
+        },
+    );
+    handle.input = input.clone();
+
+    let peeked: PeekAction = handle.peek_selector().expect("there is a selector");
+    let read: PeekAction = handle.read_selector().expect("there is a selector");
+    assert_eq!(peeked, read);
+
+    // Peeking leaves the full input available.
+    assert_eq!(PrecompileHandle::input(&handle), &input[..]);
+}
+
+#[test]
+fn peek_selector_out_of_bounds() {
+    for input in [&[][..], &[1u8, 2, 3][..]] {
+        match EvmDataReader::peek_selector(input) {
+            Err(PrecompileFailure::Revert { output, .. }) => assert_eq!(
+                output,
+                encode_revert_reason("tried to parse selector out of bounds")
+            ),
+            _ => panic!("expected a revert"),
+        }
+    }
+}
+
+#[test]
+fn read_till_end_after_typed_read() {
+    let tail = hex!("0102030405060708090a");
+    let mut input = EvmDataWriter::new().write(U256::from(42)).build();
+    input.extend_from_slice(&tail);
+
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(reader.read::<U256>().expect("to read U256"), 42.into());
+    assert_eq!(reader.read_till_end().expect("to read tail"), &tail[..]);
+
+    // Cursor is now at the end.
+    assert_eq!(reader.read_till_end().expect("to read tail"), &[] as &[u8]);
+}
+
+#[test]
+fn read_till_end_after_failed_read() {
+    let input = [0u8; 16];
+
+    let mut reader = EvmDataReader::new(&input);
+    assert!(reader.read::<U256>().is_err());
+    assert_eq!(reader.read_till_end().expect("to read tail"), &[] as &[u8]);
+}
+
+#[test]
+fn read_failure_reports_offset() {
+    let input = EvmDataWriter::new()
+        .write(U256::from(1))
+        .write(U256::from(2))
+        .build();
+
+    let mut reader = EvmDataReader::new(&input[..40]);
+    reader.read::<U256>().expect("to read 1st value");
+
+    match reader.read::<U256>() {
+        Err(PrecompileFailure::Revert { output, .. }) => assert_eq!(
+            output,
+            encode_revert_reason("Reading U256 at offset 32 would read beyond input (len 40)")
+        ),
+        _ => panic!("expected a revert"),
+    }
+}
+
+#[test]
+fn read_failure_reports_offset_in_nested_data() {
+    let mut input = EvmDataWriter::new()
+        .write(U256::from(1))
+        .write(Bytes::from(&[0xaa; 40][..]))
+        .build();
+
+    // Claim the bytes are longer than provided.
+    U256::from(100).to_big_endian(&mut input[0x40..0x60]);
+
+    let mut reader = EvmDataReader::new(&input);
+    reader.read::<U256>().expect("to read 1st value");
+
+    match reader.read::<Bytes>() {
+        Err(PrecompileFailure::Revert { output, .. }) => assert_eq!(
+            output,
+            encode_revert_reason(
+                "Reading bytes/string at offset 96 would read beyond input (len 160)"
+            )
+        ),
+        _ => panic!("expected a revert"),
+    }
+}
+
+#[test]
+fn read_field_names_the_field() {
+    let input = EvmDataWriter::new().write(U256::from(1)).build();
+
+    let mut reader = EvmDataReader::new(&input);
+    reader
+        .read_field::<U256>("from")
+        .expect("to read 1st value");
+
+    match reader.read_field::<U256>("amount") {
+        Err(PrecompileFailure::Revert { output, .. }) => assert_eq!(
+            output,
+            encode_revert_reason(
+                "amount: Reading U256 at offset 32 would read beyond input (len 32)"
+            )
+        ),
+        _ => panic!("expected a revert"),
+    }
+}
+
+#[test]
+fn decode_revert_reason_works() {
+    assert_eq!(
+        revert::decode_revert_reason(&encode_revert_reason("foo")),
+        Some("foo".to_string())
+    );
+    assert_eq!(revert::decode_revert_reason(b"foo"), None);
+}
+
+fn mock_handle() -> testing::MockHandle {
+    testing::MockHandle::default()
+        .address(H160::repeat_byte(0xff))
+        .caller(H160::repeat_byte(0xaa))
+}
+
+#[test]
+fn gasometer_records_costs() {
+    let mut handle = mock_handle();
+    handle.gas_limit = 10_000;
+
+    let mut gasometer = Gasometer::new(&mut handle);
+    gasometer.record_cost(1_000).expect("enough gas");
+    gasometer.record_log(1, 32).expect("enough gas");
+
+    let log_cost = log_costs(1, 32);
+    assert_eq!(gasometer.used_gas(), 1_000 + log_cost);
+    assert_eq!(gasometer.remaining_gas(), 10_000 - 1_000 - log_cost);
+
+    assert_eq!(handle.gas_used, 1_000 + log_cost);
+}
+
+#[test]
+fn gasometer_cumulative_costs_run_out_of_gas() {
+    let mut handle = mock_handle();
+    handle.gas_limit = 10_000;
+
+    let mut gasometer = Gasometer::new(&mut handle);
+    gasometer.record_cost(6_000).expect("enough gas");
+    gasometer.record_cost(4_000).expect("exactly enough gas");
+
+    assert_eq!(
+        gasometer.record_cost(1),
+        Err(PrecompileFailure::Error {
+            exit_status: ExitError::OutOfGas
+        })
+    );
+
+    // The failed cost is not recorded.
+    assert_eq!(gasometer.used_gas(), 10_000);
+    assert_eq!(gasometer.remaining_gas(), 0);
+}
+
+#[test]
+fn gasometer_loop_stops_when_iteration_is_not_affordable() {
+    let mut handle = mock_handle();
+    handle.gas_limit = 10_000;
+
+    let mut gasometer = Gasometer::new(&mut handle).with_reserve(2_500);
+    let mut processed = 0;
+    for _ in 0..10 {
+        if !gasometer
+            .record_iteration(1_000)
+            .expect("cost is affordable")
+        {
+            break;
+        }
+        processed += 1;
+    }
+
+    // 7 iterations fit while keeping the reserve for the final bookkeeping.
+    assert_eq!(processed, 7);
+    assert_eq!(gasometer.used_gas(), 7_000);
+    assert!(!gasometer.can_afford(1_000));
+    assert!(gasometer.can_afford(500));
+
+    gasometer
+        .record_cost(3_000)
+        .expect("reserve can be used directly");
+    assert!(!gasometer.can_afford(0));
+    assert!(!gasometer.can_afford(u64::MAX));
+}
+
+fn weighted_call(
+    ref_time: u64,
+    proof_size: u64,
+    actual: Option<(u64, u64)>,
+) -> mock::pallet_mock_calls::Call<mock::Runtime> {
+    mock::pallet_mock_calls::Call::weighted {
+        ref_time,
+        proof_size,
+        actual,
+        pays_fee: true,
+    }
+}
+
+#[test]
+fn try_dispatch_records_proof_size_if_it_is_metered() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle().proof_size_limit(1_000);
+
+        RuntimeHelper::<mock::Runtime>::try_dispatch(
+            &mut handle,
+            mock::RuntimeOrigin::root(),
+            weighted_call(1_000, 100, None),
+        )
+        .expect("enough gas and proof size");
+
+        assert_eq!(handle.gas_used, 1_000);
+        assert_eq!(handle.proof_size_used, 100);
+    })
+}
+
+#[test]
+fn try_dispatch_rejects_call_exceeding_remaining_proof_size() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle().proof_size_limit(150);
+        handle.proof_size_used = 100;
+
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::try_dispatch(
+                &mut handle,
+                mock::RuntimeOrigin::root(),
+                weighted_call(1_000, 100, None),
+            ),
+            Err(out_of_gas())
+        );
+
+        // The call is rejected before being dispatched.
+        assert_eq!(handle.gas_used, 0);
+        assert_eq!(handle.proof_size_used, 100);
+    })
+}
+
+#[test]
+fn try_dispatch_records_actual_proof_size_above_estimate() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle().proof_size_limit(1_000);
+
+        RuntimeHelper::<mock::Runtime>::try_dispatch(
+            &mut handle,
+            mock::RuntimeOrigin::root(),
+            weighted_call(1_000, 100, Some((800, 600))),
+        )
+        .expect("enough gas and proof size");
+
+        assert_eq!(handle.gas_used, 800);
+        assert_eq!(handle.proof_size_used, 600);
+    })
+}
+
+#[test]
+fn try_dispatch_fails_if_actual_proof_size_exceeds_limit() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle().proof_size_limit(500);
+
+        // The estimation fits, but not the actual proof size.
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::try_dispatch(
+                &mut handle,
+                mock::RuntimeOrigin::root(),
+                weighted_call(1_000, 100, Some((800, 600))),
+            ),
+            Err(out_of_gas())
+        );
+
+        assert_eq!(handle.gas_used, 800);
+        assert_eq!(handle.proof_size_used, 0);
+    })
+}
+
+#[test]
+fn try_dispatch_charges_proof_size_as_gas_if_it_is_not_metered() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+
+        RuntimeHelper::<mock::Runtime>::try_dispatch(
+            &mut handle,
+            mock::RuntimeOrigin::root(),
+            weighted_call(1_000, 100, None),
+        )
+        .expect("enough gas");
+
+        // A byte of proof size costs 10 gas in the mock runtime.
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::proof_size_gas_cost(100),
+            1_000
+        );
+        assert_eq!(handle.gas_used, 2_000);
+        assert_eq!(handle.proof_size_used, 0);
+    })
+}
+
+#[test]
+fn try_dispatch_requires_gas_for_proof_size_if_it_is_not_metered() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+        handle.gas_limit = 1_500;
+
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::try_dispatch(
+                &mut handle,
+                mock::RuntimeOrigin::root(),
+                weighted_call(1_000, 100, None),
+            ),
+            Err(out_of_gas())
+        );
+
+        assert_eq!(handle.gas_used, 0);
+    })
+}
+
+#[test]
+fn try_dispatch_charges_actual_proof_size_as_gas_if_it_is_not_metered() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+
+        RuntimeHelper::<mock::Runtime>::try_dispatch(
+            &mut handle,
+            mock::RuntimeOrigin::root(),
+            weighted_call(1_000, 100, Some((500, 600))),
+        )
+        .expect("enough gas");
+
+        assert_eq!(handle.gas_used, 500 + 6_000);
+    })
+}
+
+#[test]
+fn try_dispatch_charges_proof_size_as_gas_through_gas_only_handle() {
+    mock::new_test_ext().execute_with(|| {
+        // The wrapped handle meters proof size, but it is not reachable through the wrapper.
+        let mut handle = mock_handle().proof_size_limit(1_000);
+
+        RuntimeHelper::<mock::Runtime>::try_dispatch(
+            &mut GasOnlyHandle::new(&mut handle),
+            mock::RuntimeOrigin::root(),
+            weighted_call(1_000, 100, None),
+        )
+        .expect("enough gas");
+
+        assert_eq!(handle.gas_used, 2_000);
+        assert_eq!(handle.proof_size_used, 0);
+    })
+}
+
+#[test]
+fn try_dispatch_with_info_reports_free_calls() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+
+        let post_info = RuntimeHelper::<mock::Runtime>::try_dispatch_with_info(
+            &mut handle,
+            mock::RuntimeOrigin::root(),
+            mock::pallet_mock_calls::Call::<mock::Runtime>::weighted {
+                ref_time: 1_000,
+                proof_size: 0,
+                actual: Some((800, 0)),
+                pays_fee: false,
+            },
+        )
+        .expect("enough gas");
+
+        assert_eq!(post_info.pays_fee, Pays::No);
+        assert_eq!(post_info.actual_weight, Some(Weight::from_parts(800, 0)));
+
+        // Free calls are still metered.
+        assert_eq!(handle.gas_used, 800);
+    })
+}
+
+#[test]
+fn try_dispatch_with_info_reverts_if_call_fails() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+
+        assert!(matches!(
+            RuntimeHelper::<mock::Runtime>::try_dispatch_with_info(
+                &mut handle,
+                mock::RuntimeOrigin::root(),
+                mock::pallet_mock_calls::Call::<mock::Runtime>::failing { ref_time: 1_000 },
+            ),
+            Err(PrecompileFailure::Revert { .. })
+        ));
+
+        assert_eq!(handle.gas_used, 0);
+    })
+}
+
+#[test]
+fn subcall_returns_output() {
+    let mut handle = mock_handle();
+    handle.subcall_handle = Some(Box::new(|subcall: testing::Subcall| {
+        assert_eq!(subcall.address, H160::repeat_byte(0x11));
+        assert_eq!(subcall.context.caller, H160::repeat_byte(0xff));
+        assert_eq!(subcall.context.apparent_value, U256::from(5u8));
+        assert!(subcall.transfer.is_some());
+
+        testing::SubcallOutput {
+            reason: ExitReason::Succeed(ExitSucceed::Returned),
+            output: b"inner output".to_vec(),
+            cost: 1_000,
+            logs: vec![],
+        }
+    }));
+
+    let (reason, output) = subcall::call(
+        &mut handle,
+        H160::repeat_byte(0x11),
+        vec![],
+        U256::from(5u8),
+        None,
+    )
+    .expect("subcall succeeds");
+
+    assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+    assert_eq!(output, b"inner output".to_vec());
+    assert!(handle.gas_used >= 1_000);
+}
+
+#[test]
+fn subcall_revert_bubbles_up_output() {
+    let mut handle = mock_handle();
+    handle.subcall_handle = Some(Box::new(|_: testing::Subcall| testing::SubcallOutput {
+        reason: ExitReason::Revert(ExitRevert::Reverted),
+        output: encode_revert_reason("inner revert"),
+        cost: 1_000,
+        logs: vec![],
+    }));
+
+    assert_eq!(
+        subcall::call(
+            &mut handle,
+            H160::repeat_byte(0x11),
+            vec![],
+            U256::zero(),
+            Some(10_000),
+        ),
+        Err(revert(encode_revert_reason("inner revert")))
+    );
+}
+
+#[test]
+fn subcall_in_static_context_rejects_value() {
+    let mut handle = mock_handle();
+    handle.is_static = true;
+
+    assert_eq!(
+        subcall::call(
+            &mut handle,
+            H160::repeat_byte(0x11),
+            vec![],
+            U256::from(1u8),
+            None,
+        ),
+        Err(revert("can't transfer value in static context"))
+    );
+}
+
+#[derive(EvmEvent)]
+struct Transfer {
+    #[indexed]
+    from: Address,
+    #[indexed]
+    to: Address,
+    value: U256,
+}
+
+#[test]
+fn derived_event_matches_solidity_event() {
+    assert_eq!(Transfer::SIGNATURE, "Transfer(address,address,uint256)");
+    assert_eq!(
+        Transfer::TOPIC,
+        H256(hex!(
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        ))
+    );
+
+    let log = Transfer {
+        from: H160::repeat_byte(0xaa).into(),
+        to: H160::repeat_byte(0xbb).into(),
+        value: U256::from(1_000u32),
+    }
+    .to_log(H160::repeat_byte(0xff));
+
+    assert_eq!(
+        log,
+        LogsBuilder::new(H160::repeat_byte(0xff)).log3(
+            H256(keccak256!("Transfer(address,address,uint256)")),
+            H160::repeat_byte(0xaa),
+            H160::repeat_byte(0xbb),
+            EvmDataWriter::new().write(U256::from(1_000u32)).build(),
+        )
+    );
+}
+
+#[test]
+fn derived_event_is_recorded() {
+    let mut handle = mock_handle();
+    let event = || Transfer {
+        from: H160::repeat_byte(0xaa).into(),
+        to: H160::repeat_byte(0xbb).into(),
+        value: U256::from(1_000u32),
+    };
+
+    event().record(&mut handle).expect("log is recorded");
+
+    assert_eq!(
+        handle.logs,
+        vec![event().to_log(H160::repeat_byte(0xff)).into()]
+    );
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, EvmData)]
+struct Order {
+    maker: Address,
+    amount: U256,
+    data: Bytes,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, EvmData)]
+struct Point {
+    x: U256,
+    y: U256,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, EvmData)]
+struct Batch {
+    id: U256,
+    order: Order,
+}
+
+fn order() -> Order {
+    Order {
+        maker: H160::repeat_byte(0x11).into(),
+        amount: U256::from(1_000u32),
+        data: Bytes::from(&b"hello"[..]),
+    }
+}
+
+#[test]
+fn derived_struct_matches_ethers_tuple() {
+    // ethers.utils.defaultAbiCoder.encode(["tuple(address,uint256,bytes)"], [...])
+    let expected = hex!(
+        "
+        0000000000000000000000000000000000000000000000000000000000000020
+        0000000000000000000000001111111111111111111111111111111111111111
+        00000000000000000000000000000000000000000000000000000000000003e8
+        0000000000000000000000000000000000000000000000000000000000000060
+        0000000000000000000000000000000000000000000000000000000000000005
+        68656c6c6f000000000000000000000000000000000000000000000000000000
+        "
+    );
+
+    assert!(!Order::has_static_size());
+
+    let writer_output = EvmDataWriter::new().write(order()).build();
+    assert_eq!(writer_output, expected);
+
+    let mut reader = EvmDataReader::new(&expected);
+    let parsed: Order = reader.read().expect("to correctly parse Order");
+    assert_eq!(parsed, order());
+}
+
+#[test]
+fn derived_static_struct_is_inline() {
+    let point = Point {
+        x: U256::from(1u8),
+        y: U256::from(2u8),
+    };
+
+    assert!(Point::has_static_size());
+
+    let writer_output = EvmDataWriter::new().write(point.clone()).build();
+    assert_eq!(
+        writer_output,
+        EvmDataWriter::new()
+            .write(U256::from(1u8))
+            .write(U256::from(2u8))
+            .build()
+    );
+
+    let mut reader = EvmDataReader::new(&writer_output);
+    let parsed: Point = reader.read().expect("to correctly parse Point");
+    assert_eq!(parsed, point);
+}
+
+#[test]
+fn derived_nested_struct_matches_nested_tuple() {
+    let batch = Batch {
+        id: U256::from(7u8),
+        order: order(),
+    };
+
+    let writer_output = EvmDataWriter::new().write(batch.clone()).build();
+
+    let order = order();
+    assert_eq!(
+        writer_output,
+        EvmDataWriter::new()
+            .write((U256::from(7u8), (order.maker, order.amount, order.data)))
+            .build()
+    );
+
+    let mut reader = EvmDataReader::new(&writer_output);
+    let parsed: Batch = reader.read().expect("to correctly parse Batch");
+    assert_eq!(parsed, batch);
+}
+
+#[test]
+fn derived_struct_read_failure_names_field() {
+    let mut input = EvmDataWriter::new().write(order()).build();
+    input.truncate(5 * 32);
+
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(
+        reader.read::<Order>(),
+        Err(revert(encode_revert_reason(
+            "data: Reading bytes/string at offset 160 would read beyond input (len 160)"
+        )))
+    );
+}
+
+#[test]
+fn db_read_cost_records_proof_size_apart_from_gas() {
+    let mut handle = mock_handle().proof_size_limit(10_000);
+
+    handle
+        .record_db_read_cost::<mock::Runtime>(2, 300)
+        .expect("enough gas and proof size");
+
+    // The ref time of a read in the mock runtime is 1_000.
+    assert_eq!(handle.gas_used, 2_000);
+    assert_eq!(handle.proof_size_used, 600);
+
+    handle
+        .refund_db_read_cost::<mock::Runtime>(1, 300)
+        .expect("proof size is refunded");
+
+    assert_eq!(handle.gas_used, 2_000);
+    assert_eq!(handle.proof_size_used, 300);
+}
+
+#[test]
+fn db_read_cost_falls_back_to_gas() {
+    let mut handle = mock_handle();
+
+    handle
+        .record_db_read_cost::<mock::Runtime>(2, 300)
+        .expect("enough gas");
+
+    // A byte of proof size costs 10 gas in the mock runtime.
+    assert_eq!(handle.gas_used, 2_000 + 6_000);
+    assert_eq!(handle.proof_size_used, 0);
+
+    handle
+        .refund_db_read_cost::<mock::Runtime>(2, 300)
+        .expect("nothing to refund");
+
+    assert_eq!(handle.gas_used, 8_000);
+}
+
+#[test]
+fn db_read_cost_fails_if_gas_is_exceeded() {
+    let mut handle = mock_handle();
+    handle.gas_limit = 1_500;
+
+    assert_eq!(
+        handle.record_db_read_cost::<mock::Runtime>(2, 0),
+        Err(PrecompileFailure::Error {
+            exit_status: ExitError::OutOfGas
+        })
+    );
+}
+
+#[test]
+fn db_read_cost_fails_if_proof_size_is_exceeded() {
+    let mut handle = mock_handle().proof_size_limit(500);
+
+    assert_eq!(
+        handle.record_db_read_cost::<mock::Runtime>(2, 300),
+        Err(PrecompileFailure::Error {
+            exit_status: ExitError::OutOfGas
+        })
+    );
+
+    assert_eq!(handle.gas_used, 2_000);
+    assert_eq!(handle.proof_size_used, 0);
+}
+
+#[test]
+fn no_proof_size_is_free() {
+    assert_eq!(RuntimeHelper::<mock::Runtime>::proof_size_gas_cost(0), 0);
+}
+
+fn batch_handle(action: batch::Action, gas_limits: Vec<u64>) -> testing::MockHandle {
+    let mut handle = mock_handle();
+    handle.gas_limit = 100_000;
+    handle.input = EvmDataWriter::new_with_selector(action)
+        .write(vec![
+            Address::from(H160::repeat_byte(0x11)),
+            Address::from(H160::repeat_byte(0x22)),
+            Address::from(H160::repeat_byte(0x33)),
+        ])
+        .write(vec![U256::zero(); 3])
+        .write(vec![
+            Bytes::from("first"),
+            Bytes::from("second"),
+            Bytes::from("third"),
+        ])
+        .write(gas_limits)
+        .build();
+
+    // The second subcall reverts, the other ones succeed.
+    handle.subcall_handle = Some(Box::new(|subcall: testing::Subcall| {
+        assert_eq!(subcall.context.caller, H160::repeat_byte(0xaa));
+
+        let reason = if subcall.address == H160::repeat_byte(0x22) {
+            ExitReason::Revert(ExitRevert::Reverted)
+        } else {
+            ExitReason::Succeed(ExitSucceed::Returned)
+        };
+
+        testing::SubcallOutput {
+            reason,
+            output: encode_revert_reason("subcall reverted"),
+            cost: 1_000,
+            logs: vec![],
+        }
+    }));
+
+    handle
+}
+
+fn batch_log(succeeded: bool, index: u8) -> testing::PrettyLog {
+    let index = U256::from(index);
+    let address = H160::repeat_byte(0xff);
+
+    if succeeded {
+        batch::SubcallSucceeded { index }.to_log(address).into()
+    } else {
+        batch::SubcallFailed { index }.to_log(address).into()
+    }
+}
+
+#[test]
+fn batch_some_continues_after_failure() {
+    let mut handle = batch_handle(batch::Action::BatchSome, vec![0; 3]);
+
+    assert_eq!(
+        batch::BatchPrecompile::execute(&mut handle),
+        Ok(succeed([]))
+    );
+    assert_eq!(
+        handle.logs,
+        vec![batch_log(true, 0), batch_log(false, 1), batch_log(true, 2)]
+    );
+}
+
+#[test]
+fn batch_some_until_failure_stops_without_reverting() {
+    let mut handle = batch_handle(batch::Action::BatchSomeUntilFailure, vec![0; 3]);
+
+    assert_eq!(
+        batch::BatchPrecompile::execute(&mut handle),
+        Ok(succeed([]))
+    );
+    assert_eq!(handle.logs, vec![batch_log(true, 0), batch_log(false, 1)]);
+}
+
+#[test]
+fn batch_all_reverts_on_failure() {
+    let mut handle = batch_handle(batch::Action::BatchAll, vec![0; 3]);
+
+    assert_eq!(
+        batch::BatchPrecompile::execute(&mut handle),
+        Err(revert(encode_revert_reason("subcall reverted")))
+    );
+}
+
+#[test]
+fn batch_fails_subcall_with_gas_limit_above_remaining_gas() {
+    let mut handle = batch_handle(batch::Action::BatchSome, vec![0, 1_000_000, 0]);
+
+    assert_eq!(
+        batch::BatchPrecompile::execute(&mut handle),
+        Ok(succeed([]))
+    );
+    assert_eq!(
+        handle.logs,
+        vec![batch_log(true, 0), batch_log(false, 1), batch_log(true, 2)]
+    );
+}
+
+#[test]
+fn batch_reverts_on_mismatched_array_lengths() {
+    let mut handle = batch_handle(batch::Action::BatchSome, vec![0; 2]);
+
+    assert_eq!(
+        batch::BatchPrecompile::execute(&mut handle),
+        Err(revert(encode_revert_reason(
+            "arrays must have the same length"
+        )))
+    );
+    assert!(handle.logs.is_empty());
+}
+
+#[test]
+fn batch_reverts_when_called_with_delegatecall() {
+    let mut handle = batch_handle(batch::Action::BatchSome, vec![0; 3]);
+    handle.context.address = H160::repeat_byte(0xbb);
+
+    assert_eq!(
+        batch::BatchPrecompile::execute(&mut handle),
+        Err(revert(encode_revert_reason(
+            "cannot be called with DELEGATECALL"
+        )))
+    );
+    assert!(handle.logs.is_empty());
+}
+
+#[test]
+fn check_function_modifier_value_combinations() {
+    use FunctionModifier::*;
+
+    let static_error = Err(revert("can't call non-static function in static context"));
+    let payable_error = Err(revert("function is not payable"));
+
+    // (is_static, value, modifier, expected)
+    let cases = [
+        (false, 0u8, View, Ok(false)),
+        (false, 0, NonPayable, Ok(false)),
+        (false, 0, Payable, Ok(false)),
+        (false, 1, View, payable_error.clone()),
+        (false, 1, NonPayable, payable_error.clone()),
+        (false, 1, Payable, Ok(true)),
+        (true, 0, View, Ok(false)),
+        (true, 0, NonPayable, static_error.clone()),
+        (true, 0, Payable, static_error.clone()),
+        (true, 1, View, payable_error.clone()),
+        (true, 1, NonPayable, static_error.clone()),
+        (true, 1, Payable, static_error.clone()),
+    ];
+
+    for (is_static, value, modifier, expected) in cases {
+        let mut handle = mock_handle();
+        handle.is_static = is_static;
+        handle.context.apparent_value = U256::from(value);
+
+        assert_eq!(
+            handle.check_function_modifier_value(modifier),
+            expected,
+            "static: {}, value: {}, modifier: {:?}",
+            is_static,
+            value,
+            modifier
+        );
+        assert_eq!(
+            handle.check_function_modifier(modifier),
+            expected.map(|_| ())
+        );
+    }
+}
+
+#[test]
+fn non_zero_address_round_trip() {
+    let address = NonZeroAddress(H160::repeat_byte(0x11));
+
+    let writer_output = EvmDataWriter::new().write(address).build();
+    assert_eq!(
+        writer_output,
+        EvmDataWriter::new()
+            .write(Address(H160::repeat_byte(0x11)))
+            .build()
+    );
+
+    let mut reader = EvmDataReader::new(&writer_output);
+    let parsed: NonZeroAddress = reader.read().expect("to correctly parse NonZeroAddress");
+    assert_eq!(parsed, address);
+}
+
+#[test]
+fn non_zero_address_rejects_zero() {
+    let input = EvmDataWriter::new().write(Address(H160::zero())).build();
+
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(
+        reader.read::<NonZeroAddress>(),
+        Err(revert(encode_revert_reason("expected non-zero address")))
+    );
+}
+
+#[test]
+fn non_zero_address_rejects_dirty_high_bytes() {
+    let mut input = EvmDataWriter::new()
+        .write(Address(H160::repeat_byte(0x11)))
+        .build();
+    input[0] = 0x01;
+
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(
+        reader.read::<NonZeroAddress>(),
+        Err(revert(encode_revert_reason("address has dirty high bytes")))
+    );
+}
+
+#[test]
+fn strict_address_rejects_dirty_high_bytes() {
+    let mut input = EvmDataWriter::new()
+        .write(Address(H160::repeat_byte(0x11)))
+        .build();
+    // Last byte of the padding.
+    input[11] = 0x01;
+
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(
+        reader.read::<StrictAddress>(),
+        Err(revert(encode_revert_reason("address has dirty high bytes")))
+    );
+
+    // `Address` ignores the upper bytes.
+    let mut reader = EvmDataReader::new(&input);
+    let parsed: Address = reader.read().expect("to correctly parse Address");
+    assert_eq!(parsed, Address(H160::repeat_byte(0x11)));
+}
+
+#[test]
+fn strict_address_accepts_left_padded_address() {
+    let mut input = EvmDataWriter::new().write(Address(H160::zero())).build();
+    // First byte of the address.
+    input[12] = 0x01;
+
+    let mut expected = H160::zero();
+    expected.0[0] = 0x01;
+
+    let mut reader = EvmDataReader::new(&input);
+    let parsed: StrictAddress = reader.read().expect("to correctly parse StrictAddress");
+    assert_eq!(parsed, StrictAddress(expected));
+
+    let writer_output = EvmDataWriter::new().write(parsed).build();
+    assert_eq!(writer_output, input);
+}
+
+#[test]
+fn fixed_bytes4_selector_round_trip() {
+    let selector = FixedBytes(hex!("a9059cbb"));
+
+    let writer_output = EvmDataWriter::new().write(selector).build();
+    assert_eq!(
+        writer_output,
+        hex!("a9059cbb00000000000000000000000000000000000000000000000000000000")
+    );
+
+    let mut reader = EvmDataReader::new(&writer_output);
+    let parsed: FixedBytes<4> = reader.read().expect("to correctly parse bytes4");
+    assert_eq!(parsed, selector);
+}
+
+#[test]
+fn fixed_bytes32_round_trip() {
+    let value = FixedBytes([0xab; 32]);
+
+    let writer_output = EvmDataWriter::new().write(value).build();
+    assert_eq!(writer_output, vec![0xab; 32]);
+
+    let mut reader = EvmDataReader::new(&writer_output);
+    let parsed: FixedBytes<32> = reader.read().expect("to correctly parse bytes32");
+    assert_eq!(parsed, value);
+}
+
+#[test]
+fn fixed_bytes_rejects_non_zero_padding() {
+    // Last byte of the value.
+    let mut input = [0u8; 32];
+    input[3] = 0x01;
+
+    let mut reader = EvmDataReader::new(&input);
+    let parsed: FixedBytes<4> = reader.read().expect("to correctly parse bytes4");
+    assert_eq!(parsed, FixedBytes([0, 0, 0, 1]));
+
+    // First byte of the padding.
+    let mut input = [0u8; 32];
+    input[4] = 0x01;
+
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(
+        reader.read::<FixedBytes<4>>(),
+        Err(revert(encode_revert_reason("bytesN has non-zero padding")))
+    );
+}
+
+#[test]
+fn expect_end_accepts_fully_read_input() {
+    let input = EvmDataWriter::new()
+        .write(U256::from(1u8))
+        .write(Address(H160::repeat_byte(0x11)))
+        .build();
+
+    let mut reader = EvmDataReader::new(&input);
+    let _: U256 = reader.read().expect("to correctly parse U256");
+    let _: Address = reader.read().expect("to correctly parse Address");
+
+    assert_eq!(reader.expect_end(), Ok(()));
+}
+
+#[test]
+fn expect_end_rejects_trailing_bytes() {
+    let mut input = EvmDataWriter::new().write(U256::from(1u8)).build();
+    input.extend_from_slice(&[0xff; 5]);
+
+    let mut reader = EvmDataReader::new(&input);
+    let _: U256 = reader.read().expect("to correctly parse U256");
+
+    assert_eq!(
+        reader.expect_end(),
+        Err(revert(encode_revert_reason("unexpected trailing 5 bytes")))
+    );
+}
+
+#[test]
+fn expect_end_accepts_dynamic_tail() {
+    let input = EvmDataWriter::new_with_selector(0x12345678u32)
+        .write(Bytes::from("hello"))
+        .write(vec![U256::from(1u8), U256::from(2u8)])
+        .write(U256::from(3u8))
+        .build();
+
+    let mut reader = EvmDataReader::new_skip_selector(&input).expect("to skip selector");
+    let _: Bytes = reader.read().expect("to correctly parse Bytes");
+    let _: Vec<U256> = reader.read().expect("to correctly parse Vec<U256>");
+    let _: U256 = reader.read().expect("to correctly parse U256");
+
+    assert_eq!(reader.expect_end(), Ok(()));
+}
+
+#[test]
+fn expect_end_accepts_nested_dynamic_tail() {
+    let input = EvmDataWriter::new()
+        .write(vec![Bytes::from("hello"), Bytes::from("world")])
+        .build();
+
+    let mut reader = EvmDataReader::new(&input);
+    let _: Vec<Bytes> = reader.read().expect("to correctly parse Vec<Bytes>");
+
+    assert_eq!(reader.expect_end(), Ok(()));
+}
+
+#[test]
+fn expect_end_rejects_trailing_bytes_after_dynamic_tail() {
+    let mut input = EvmDataWriter::new().write(Bytes::from("hello")).build();
+    input.extend_from_slice(&[0u8; 32]);
+
+    let mut reader = EvmDataReader::new(&input);
+    let _: Bytes = reader.read().expect("to correctly parse Bytes");
+
+    assert_eq!(
+        reader.expect_end(),
+        Err(revert(encode_revert_reason("unexpected trailing 32 bytes")))
+    );
+}
+
+#[test]
+fn failure_helpers() {
+    assert_eq!(
+        out_of_gas(),
+        PrecompileFailure::Error {
+            exit_status: ExitError::OutOfGas
+        }
+    );
+    assert_eq!(
+        error_other("foo"),
+        PrecompileFailure::Error {
+            exit_status: ExitError::Other("foo".into())
+        }
+    );
+    assert_eq!(error("foo"), error_other("foo"));
+}
+
+#[test]
+fn log_costs_saturates_on_overflow() {
+    assert_eq!(log_costs(1, 32), 375 + 375 + 8 * 32);
+    assert_eq!(log_costs_checked(1, 32), Ok(log_costs(1, 32)));
+
+    // Largest data length whose cost doesn't overflow.
+    let max_data_len = ((u64::MAX - 375) / 8) as usize;
+    assert_eq!(log_costs(0, max_data_len), 375 + 8 * max_data_len as u64);
+    assert_eq!(
+        log_costs_checked(0, max_data_len),
+        Ok(log_costs(0, max_data_len))
+    );
+
+    assert_eq!(log_costs(0, max_data_len + 1), u64::MAX);
+    assert_eq!(log_costs_checked(0, max_data_len + 1), Err(out_of_gas()));
+
+    assert_eq!(log_costs(usize::MAX, usize::MAX), u64::MAX);
+    assert_eq!(log_costs_checked(usize::MAX, 0), Err(out_of_gas()));
+}
+
+#[test]
+fn saturated_log_costs_fail_to_be_recorded() {
+    let mut handle = mock_handle();
+    handle.gas_limit = 100_000;
+
+    assert_eq!(
+        handle.record_log_costs_manual(0, usize::MAX),
+        Err(out_of_gas())
+    );
+    assert_eq!(handle.record_cost(u64::MAX), Err(ExitError::OutOfGas));
+}
+
+#[test]
+fn mock_handle_builder_sets_context() {
+    let handle = testing::MockHandle::default()
+        .address(H160::repeat_byte(0xff))
+        .caller(H160::repeat_byte(0xaa))
+        .value(1u8)
+        .gas_limit(100_000)
+        .static_call(true)
+        .input(vec![1, 2, 3]);
+
+    assert_eq!(handle.code_address(), H160::repeat_byte(0xff));
+    assert_eq!(handle.context().address, H160::repeat_byte(0xff));
+    assert_eq!(handle.context().caller, H160::repeat_byte(0xaa));
+    assert_eq!(handle.context().apparent_value, U256::one());
+    assert_eq!(PrecompileHandle::gas_limit(&handle), Some(100_000));
+    assert_eq!(handle.remaining_gas(), 100_000);
+    assert!(handle.is_static());
+    assert_eq!(PrecompileHandle::input(&handle), &[1, 2, 3]);
+}
+
+#[test]
+fn mock_handle_out_of_gas_consumes_all_gas() {
+    let mut handle = testing::MockHandle::default().gas_limit(1_000);
+
+    assert_eq!(handle.record_cost(600), Ok(()));
+    assert_eq!(handle.gas_used(), 600);
+
+    assert_eq!(handle.record_cost(600), Err(ExitError::OutOfGas));
+    assert_eq!(handle.gas_used(), 1_000);
+    assert_eq!(handle.remaining_gas(), 0);
+}
+
+struct DoublePrecompile;
+
+impl Precompile for DoublePrecompile {
+    fn execute(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        handle.record_cost(500)?;
+
+        let value: U256 = EvmDataReader::new(handle.input()).read()?;
+        let doubled = value
+            .checked_mul(2u8.into())
+            .ok_or_else(|| revert("overflow"))?;
+
+        handle.record_log_costs_manual(1, 32)?;
+        LogsBuilder::new(handle.context().address)
+            .log1(
+                H256::repeat_byte(0x01),
+                EvmDataWriter::new().write(doubled).build(),
+            )
+            .record(handle)?;
+
+        Ok(succeed(EvmDataWriter::new().write(doubled).build()))
+    }
+}
+
+#[test]
+fn mock_handle_runs_precompile_end_to_end() {
+    let mut handle = mock_handle()
+        .gas_limit(100_000)
+        .input(EvmDataWriter::new().write(U256::from(21u8)).build());
+
+    let output = DoublePrecompile::execute(&mut handle).expect("precompile succeeds");
+
+    assert_eq!(
+        output.output,
+        EvmDataWriter::new().write(U256::from(42u8)).build()
+    );
+    assert_eq!(handle.gas_used(), 500 + log_costs(1, 32));
+    assert_eq!(
+        handle.logs(),
+        vec![LogsBuilder::new(H160::repeat_byte(0xff)).log1(
+            H256::repeat_byte(0x01),
+            EvmDataWriter::new().write(U256::from(42u8)).build(),
+        )]
+    );
+}
+
+#[test]
+fn mock_handle_runs_precompile_out_of_gas() {
+    let mut handle = mock_handle()
+        .gas_limit(499)
+        .input(EvmDataWriter::new().write(U256::from(21u8)).build());
+
+    assert_eq!(
+        DoublePrecompile::execute(&mut handle).map(|output| output.output),
+        Err(out_of_gas())
+    );
+    assert_eq!(handle.gas_used(), 499);
+    assert!(handle.logs().is_empty());
+}
+
+#[test]
+fn assert_revert_eq_matches_reason() {
+    testing::assert_revert_eq::<()>(Err(revert_with_reason("message")), "message");
+    testing::assert_revert_eq::<()>(Err(revert("raw message")), "raw message");
+    testing::assert_revert_eq::<()>(Err(revert(hex!("0102"))), hex!("0102"));
+}
+
+#[test]
+#[should_panic]
+fn assert_revert_eq_fails_on_other_reason() {
+    testing::assert_revert_eq::<()>(Err(revert_with_reason("message")), "other message");
+}
+
+#[test]
+#[should_panic(expected = "expected raw revert output")]
+fn assert_revert_eq_fails_on_other_raw_output() {
+    testing::assert_revert_eq::<()>(Err(revert("raw message")), "other message");
+}
+
+#[test]
+#[should_panic(expected = "expected a revert")]
+fn assert_revert_eq_fails_if_not_reverting() {
+    testing::assert_revert_eq(Ok(succeed([])), "message");
+}
+
+#[test]
+#[should_panic(expected = "expected a revert")]
+fn assert_revert_eq_fails_on_error() {
+    testing::assert_revert_eq::<()>(Err(out_of_gas()), "message");
+}
+
+#[test]
+fn assert_event_finds_recorded_log() {
+    let mut handle = mock_handle();
+    let logs = LogsBuilder::new(H160::repeat_byte(0xff));
+
+    logs.log0(vec![1])
+        .record(&mut handle)
+        .expect("not out of gas");
+    logs.log1(H256::repeat_byte(0x01), vec![2])
+        .record(&mut handle)
+        .expect("not out of gas");
+
+    testing::assert_event(&handle, logs.log0(vec![1]));
+    testing::assert_event(&handle, logs.log1(H256::repeat_byte(0x01), vec![2]));
+}
+
+#[test]
+#[should_panic(expected = "to be recorded")]
+fn assert_event_fails_on_other_data() {
+    let mut handle = mock_handle();
+    let logs = LogsBuilder::new(H160::repeat_byte(0xff));
+
+    logs.log1(H256::repeat_byte(0x01), vec![2])
+        .record(&mut handle)
+        .expect("not out of gas");
+
+    testing::assert_event(&handle, logs.log1(H256::repeat_byte(0x01), vec![3]));
+}
+
+#[test]
+#[should_panic(expected = "to be recorded")]
+fn assert_event_fails_on_other_address() {
+    let mut handle = mock_handle();
+
+    LogsBuilder::new(H160::repeat_byte(0xff))
+        .log0(vec![1])
+        .record(&mut handle)
+        .expect("not out of gas");
+
+    testing::assert_event(
+        &handle,
+        LogsBuilder::new(H160::repeat_byte(0xee)).log0(vec![1]),
+    );
+}
+
+struct ViewPrecompile;
+
+impl Precompile for ViewPrecompile {
+    fn execute(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        handle.record_cost(RuntimeHelper::<mock::Runtime>::db_read_gas_cost())?;
+
+        Ok(succeed(
+            EvmDataWriter::new().write(U256::from(42u8)).build(),
+        ))
+    }
+}
+
+#[test]
+fn run_view_function_consumes_base_cost() {
+    let mut handle = mock_handle();
+
+    let (result, gas_used) = handle.run::<ViewPrecompile>(vec![]);
+
+    assert_eq!(
+        result,
+        Ok(succeed(
+            EvmDataWriter::new().write(U256::from(42u8)).build()
+        ))
+    );
+    assert_eq!(gas_used, 1_000);
+}
+
+#[test]
+fn run_includes_log_costs() {
+    let mut handle = mock_handle();
+
+    let (result, gas_used) =
+        handle.run::<DoublePrecompile>(EvmDataWriter::new().write(U256::from(21u8)).build());
+
+    assert!(result.is_ok());
+    assert_eq!(gas_used, 1_506);
+}
+
+#[test]
+fn run_reports_gas_of_each_run() {
+    let mut handle = mock_handle().gas_limit(1_500);
+
+    let (_, gas_used) = handle.run::<ViewPrecompile>(vec![]);
+    assert_eq!(gas_used, 1_000);
+
+    let (result, gas_used) = handle.run::<ViewPrecompile>(vec![]);
+    assert_eq!(result, Err(out_of_gas()));
+    assert_eq!(gas_used, 500);
+}
+
+#[test]
+fn read_pointer_targets_pointed_data() {
+    let input = EvmDataWriter::new()
+        .write(U256::from(0x40))
+        .write(U256::from(1u8))
+        .write(U256::from(2u8))
+        .write(U256::from(3u8))
+        .build();
+
+    let mut reader = EvmDataReader::new(&input);
+    let mut pointed = reader.read_pointer().expect("valid pointer");
+
+    assert_eq!(pointed.read::<U256>(), Ok(U256::from(2u8)));
+    assert_eq!(pointed.read::<U256>(), Ok(U256::from(3u8)));
+    assert_eq!(reader.read::<U256>(), Ok(U256::from(1u8)));
+}
+
+#[test]
+fn read_pointer_is_relative_to_nested_reader() {
+    let input = EvmDataWriter::new()
+        .write(U256::from(0x20))
+        .write(U256::from(0x20))
+        .write(U256::from(42u8))
+        .build();
+
+    let mut reader = EvmDataReader::new(&input);
+    let mut inner = reader.read_pointer().expect("valid pointer");
+    let mut pointed = inner.read_pointer().expect("valid pointer");
+
+    assert_eq!(pointed.read::<U256>(), Ok(U256::from(42u8)));
+}
+
+#[test]
+fn read_pointer_out_of_bounds() {
+    let input = EvmDataWriter::new().write(U256::from(0x20)).build();
+
+    assert_eq!(
+        EvmDataReader::new(&input).read_pointer().map(|_| ()),
+        Err(RevertReason::PointerToOutofbound.into())
+    );
+
+    let input = EvmDataWriter::new()
+        .write(U256::from(0x1000))
+        .write(U256::zero())
+        .build();
+
+    assert_eq!(
+        EvmDataReader::new(&input).read_pointer().map(|_| ()),
+        Err(revert_with_reason("pointer out of bounds"))
+    );
+
+    let input = EvmDataWriter::new().write(U256::MAX).build();
+
+    assert_eq!(
+        EvmDataReader::new(&input).read_pointer().map(|_| ()),
+        Err(revert_with_reason("pointer is too large"))
+    );
+}
+
+#[test]
+fn write_reserved_pointers() {
+    fn bytes_data(value: &[u8]) -> Vec<u8> {
+        let mut data = EvmDataWriter::new().write(U256::from(value.len())).build();
+        data.extend_from_slice(value);
+        data.resize(32 + (value.len() + 31) / 32 * 32, 0);
+        data
+    }
+
+    let first = b"first".to_vec();
+    let second = vec![0x22; 40];
+
+    let mut writer = EvmDataWriter::new();
+    let first_slot = writer.reserve_pointer();
+    let second_slot = writer.reserve_pointer();
+    writer.write_data(first_slot, bytes_data(&first));
+    writer.write_data(second_slot, bytes_data(&second));
+    let output = writer.build();
+
+    assert_eq!(
+        output,
+        EvmDataWriter::new()
+            .write(Bytes(first.clone()))
+            .write(Bytes(second.clone()))
+            .build()
+    );
+
+    let mut reader = EvmDataReader::new(&output);
+    assert_eq!(reader.read::<Bytes>(), Ok(Bytes(first)));
+    assert_eq!(reader.read::<Bytes>(), Ok(Bytes(second)));
+    assert_eq!(reader.expect_end(), Ok(()));
+}
+
+#[test]
+fn write_reserved_pointers_in_any_order() {
+    let mut writer = EvmDataWriter::new();
+    let first_slot = writer.reserve_pointer();
+    let second_slot = writer.reserve_pointer();
+    writer.write_data(
+        second_slot,
+        EvmDataWriter::new().write(U256::from(2u8)).build(),
+    );
+    writer.write_data(
+        first_slot,
+        EvmDataWriter::new().write(U256::from(1u8)).build(),
+    );
+    let output = writer.build();
+
+    let mut reader = EvmDataReader::new(&output);
+    assert_eq!(
+        reader.read_pointer().and_then(|mut r| r.read::<U256>()),
+        Ok(U256::from(1u8))
+    );
+    assert_eq!(
+        reader.read_pointer().and_then(|mut r| r.read::<U256>()),
+        Ok(U256::from(2u8))
+    );
+}
+
+#[test]
+fn indexed_dynamic_topic() {
+    // event Message(string indexed message) emitted with "hello".
+    assert_eq!(
+        indexed_dynamic(b"hello"),
+        H256(hex!(
+            "1c8aff950685c2ed4bc3174f3472287b56d9517b9c948127319a09a7a36deac8"
+        ))
+    );
+    assert_eq!(indexed_dynamic(b"hello"), H256(keccak256!("hello")));
+
+    // Unlike value types, the topic isn't the padded value.
+    assert_ne!(indexed_dynamic(&[1]), encode_topic(FixedBytes([1u8])));
+}
+
+#[test]
+fn log_costs_total_sums_logs() {
+    let logs = LogsBuilder::new(H160::repeat_byte(0xff));
+    let log0 = logs.log0(vec![]);
+    let log2 = logs.log2(H256::repeat_byte(1), H256::repeat_byte(2), vec![0; 32]);
+    let log4 = logs.log4(
+        H256::repeat_byte(1),
+        H256::repeat_byte(2),
+        H256::repeat_byte(3),
+        H256::repeat_byte(4),
+        vec![0; 100],
+    );
+
+    assert_eq!(
+        log_costs_total(&[&log0, &log2, &log4]),
+        Ok(375 + (375 + 2 * 375 + 8 * 32) + (375 + 4 * 375 + 8 * 100))
+    );
+    assert_eq!(log_costs_total(&[]), Ok(0));
+
+    let mut handle = mock_handle();
+    handle
+        .record_log_costs(&[&log0, &log2, &log4])
+        .expect("not out of gas");
+    assert_eq!(
+        log_costs_total(&[&log0, &log2, &log4]),
+        Ok(handle.gas_used())
+    );
+}
+
+#[test]
+fn writer_capacity_doesnt_change_output() {
+    let array: Vec<U256> = (0..1000u32).map(U256::from).collect();
+
+    let output = EvmDataWriter::with_capacity(1002)
+        .write(array.clone())
+        .build();
+
+    assert_eq!(output, EvmDataWriter::new().write(array.clone()).build());
+    assert_eq!(EvmDataReader::new(&output).read::<Vec<U256>>(), Ok(array));
+
+    assert_eq!(
+        EvmDataWriter::with_capacity(0).write(U256::one()).build(),
+        EvmDataWriter::new().write(U256::one()).build()
+    );
+}
+
+#[test]
+fn db_gas_cost_n() {
+    type Helper = RuntimeHelper<mock::Runtime>;
+
+    assert_eq!(
+        Helper::db_read_gas_cost_n(3),
+        3 * Helper::db_read_gas_cost()
+    );
+    assert_eq!(
+        Helper::db_write_gas_cost_n(3),
+        3 * Helper::db_write_gas_cost()
+    );
+
+    assert_eq!(Helper::db_read_gas_cost_n(1), Helper::db_read_gas_cost());
+    assert_eq!(Helper::db_write_gas_cost_n(0), 0);
+}
+
+#[derive(Debug, PartialEq, Eq, num_enum::TryFromPrimitive)]
+#[repr(u8)]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+#[test]
+fn read_enum() {
+    let input = EvmDataWriter::new().write(0u8).write(2u8).build();
+    let mut reader = EvmDataReader::new(&input);
+
+    assert_eq!(reader.read_enum::<Color>(), Ok(Color::Red));
+    assert_eq!(reader.read_enum::<Color>(), Ok(Color::Blue));
+
+    let input = EvmDataWriter::new().write(1u8).build();
+    assert_eq!(
+        EvmDataReader::new(&input).read_enum::<Color>(),
+        Ok(Color::Green)
+    );
+}
+
+#[test]
+fn read_enum_rejects_invalid_value() {
+    let input = EvmDataWriter::new().write(5u8).build();
+
+    assert_eq!(
+        EvmDataReader::new(&input).read_enum::<Color>(),
+        Err(revert_with_reason("invalid enum value 5"))
+    );
+
+    let input = EvmDataWriter::new().write(256u32).build();
+
+    assert_eq!(
+        EvmDataReader::new(&input).read_enum::<Color>(),
+        Err(revert_with_reason("value too large for uint8"))
+    );
+}
+
+#[test]
+fn read_nonzero_u256() {
+    let input = EvmDataWriter::new()
+        .write(U256::from(42u8))
+        .write(U256::zero())
+        .build();
+    let mut reader = EvmDataReader::new(&input);
+
+    assert_eq!(reader.read_nonzero_u256("amount"), Ok(U256::from(42u8)));
+    assert_eq!(
+        reader.read_nonzero_u256("price"),
+        Err(revert_with_reason("price must be nonzero"))
+    );
+}
+
+#[generate_function_selector]
+#[derive(Debug, PartialEq)]
+enum DispatchAction {
+    #[view]
+    Get = "get()",
+    #[alias("store(uint256)")]
+    Set = "set(uint256)",
+    #[payable]
+    Deposit = "deposit()",
+}
+
+struct DispatchPrecompile;
+
+impl DispatchPrecompile {
+    fn get(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        handle.record_cost(100)?;
+        Ok(succeed(EvmDataWriter::new().write(U256::from(1u8)).build()))
+    }
+
+    fn set(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        handle.record_cost(200)?;
+        let value: U256 = handle.read_input()?.read()?;
+        Ok(succeed(EvmDataWriter::new().write(value).build()))
+    }
+
+    fn deposit(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        handle.record_cost(300)?;
+        Ok(succeed(
+            EvmDataWriter::new()
+                .write(handle.context().apparent_value)
+                .build(),
+        ))
+    }
+}
+
+impl Precompile for DispatchPrecompile {
+    fn execute(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        dispatch!(handle, DispatchAction {
+            Get => Self::get,
+            Set => Self::set,
+            Deposit => Self::deposit,
+        })
+    }
+}
+
+#[test]
+fn dispatch_routes_to_handlers() {
+    let mut handle = mock_handle();
+    let (result, gas_used) = handle
+        .run::<DispatchPrecompile>(EvmDataWriter::new_with_selector(DispatchAction::Get).build());
+    assert_eq!(
+        result,
+        Ok(succeed(EvmDataWriter::new().write(U256::from(1u8)).build()))
+    );
+    assert_eq!(gas_used, 100);
+
+    let (result, gas_used) = handle.run::<DispatchPrecompile>(
+        EvmDataWriter::new_with_selector(DispatchAction::Set)
+            .write(U256::from(42u8))
+            .build(),
+    );
+    assert_eq!(
+        result,
+        Ok(succeed(
+            EvmDataWriter::new().write(U256::from(42u8)).build()
+        ))
+    );
+    assert_eq!(gas_used, 200);
+
+    let mut handle = mock_handle().value(5u8);
+    let (result, gas_used) = handle.run::<DispatchPrecompile>(
+        EvmDataWriter::new_with_selector(DispatchAction::Deposit).build(),
+    );
+    assert_eq!(
+        result,
+        Ok(succeed(EvmDataWriter::new().write(U256::from(5u8)).build()))
+    );
+    assert_eq!(gas_used, 300);
+}
+
+#[test]
+fn dispatch_rejects_value_for_non_payable_before_handler() {
+    let mut handle = mock_handle().value(1u8);
+
+    let (result, gas_used) = handle.run::<DispatchPrecompile>(
+        EvmDataWriter::new_with_selector(DispatchAction::Set)
+            .write(U256::from(42u8))
+            .build(),
+    );
+
+    testing::assert_revert_eq(result, "function is not payable");
+    assert_eq!(gas_used, 0);
+}
+
+#[test]
+fn dispatch_checks_static_context_before_handler() {
+    let mut handle = mock_handle().static_call(true);
+
+    let (result, gas_used) = handle.run::<DispatchPrecompile>(
+        EvmDataWriter::new_with_selector(DispatchAction::Deposit).build(),
+    );
+    testing::assert_revert_eq(result, "can't call non-static function in static context");
+    assert_eq!(gas_used, 0);
+
+    let (result, gas_used) = handle
+        .run::<DispatchPrecompile>(EvmDataWriter::new_with_selector(DispatchAction::Get).build());
+    assert!(result.is_ok());
+    assert_eq!(gas_used, 100);
+}
+
+#[test]
+fn dispatch_rejects_unknown_selector() {
+    let mut handle = mock_handle();
+
+    let (result, gas_used) = handle.run::<DispatchPrecompile>(hex!("12345678").to_vec());
+
+    testing::assert_revert_eq(result, "unknown selector");
+    assert_eq!(gas_used, 0);
+}
+
+#[test]
+fn subcall_decode_output() {
+    let mut handle = mock_handle();
+    handle.subcall_handle = Some(Box::new(|_: testing::Subcall| testing::SubcallOutput {
+        reason: ExitReason::Succeed(ExitSucceed::Returned),
+        output: EvmDataWriter::new()
+            .write(true)
+            .write(U256::from(42u8))
+            .build(),
+        cost: 1_000,
+        logs: vec![],
+    }));
+
+    let (_, output) = subcall::call(
+        &mut handle,
+        H160::repeat_byte(0x11),
+        vec![],
+        U256::zero(),
+        None,
+    )
+    .expect("subcall succeeds");
+
+    assert_eq!(
+        subcall::decode_output::<(bool, U256)>(&output),
+        Ok((true, U256::from(42u8)))
+    );
+}
+
+#[test]
+fn subcall_decode_output_too_short() {
+    let output = EvmDataWriter::new().write(true).build();
+
+    assert_eq!(
+        subcall::decode_output::<(bool, U256)>(&output),
+        Err(revert_with_reason(
+            "Reading U256 at offset 32 would read beyond input (len 32)"
+        ))
+    );
+}
+
+#[test]
+fn subcall_decode_output_rejects_trailing_bytes() {
+    let output = EvmDataWriter::new()
+        .write(true)
+        .write(U256::from(42u8))
+        .build();
+
+    assert_eq!(
+        subcall::decode_output::<bool>(&output),
+        Err(revert_with_reason("unexpected trailing 32 bytes"))
+    );
+}
+
+#[test]
+fn read_u256_requires_full_word() {
+    // U256 reads are not zero-extended when less than 32 bytes remain.
+    let mut input = EvmDataWriter::new().write(U256::from(42u8)).build();
+    input.extend_from_slice(&[0x11; 20]);
+
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(reader.read::<U256>(), Ok(U256::from(42u8)));
+    assert_eq!(
+        reader.read::<U256>(),
+        Err(revert_with_reason(
+            "Reading U256 at offset 32 would read beyond input (len 52)"
+        ))
+    );
+
+    assert_eq!(
+        EvmDataReader::new(&[0x11; 20]).read::<U256>(),
+        Err(revert_with_reason(
+            "Reading U256 at offset 0 would read beyond input (len 20)"
+        ))
+    );
+}
+
+fn oversized_output_handle(reason: ExitReason) -> testing::MockHandle {
+    mock_handle().subcall_handle(move |_: testing::Subcall| testing::SubcallOutput {
+        reason: reason.clone(),
+        output: vec![0x11; 100],
+        cost: 1_000,
+        logs: vec![],
+    })
+}
+
+fn bounded_call(handle: &mut testing::MockHandle, max_output_len: usize) -> EvmResult<Vec<u8>> {
+    subcall::call_from_bounded(
+        handle,
+        H160::repeat_byte(0xff),
+        H160::repeat_byte(0x11),
+        vec![],
+        U256::zero(),
+        None,
+        max_output_len,
+    )
+    .map(|(_, output)| output)
+}
+
+#[test]
+fn subcall_bounded_output_rejects_oversized_output() {
+    let mut handle = oversized_output_handle(ExitReason::Succeed(ExitSucceed::Returned));
+
+    assert_eq!(
+        bounded_call(&mut handle, 64),
+        Err(revert_with_reason("return data too large"))
+    );
+}
+
+#[test]
+fn subcall_bounded_output_rejects_oversized_revert_output() {
+    let mut handle = oversized_output_handle(ExitReason::Revert(ExitRevert::Reverted));
+
+    assert_eq!(
+        bounded_call(&mut handle, 64),
+        Err(revert_with_reason("return data too large"))
+    );
+}
+
+#[test]
+fn subcall_bounded_output_records_memory_cost() {
+    let mut handle = oversized_output_handle(ExitReason::Succeed(ExitSucceed::Returned));
+    subcall::call_from(
+        &mut handle,
+        H160::repeat_byte(0xff),
+        H160::repeat_byte(0x11),
+        vec![],
+        U256::zero(),
+        None,
+    )
+    .expect("subcall succeeds");
+    let unbounded_gas_used = handle.gas_used();
+
+    let mut handle = oversized_output_handle(ExitReason::Succeed(ExitSucceed::Returned));
+    assert_eq!(bounded_call(&mut handle, 100), Ok(vec![0x11; 100]));
+
+    // 4 words of memory.
+    assert_eq!(handle.gas_used(), unbounded_gas_used + 3 * 4);
+}
+
+#[test]
+fn handle_context_accessors() {
+    let mut handle = testing::MockHandle::default()
+        .address(H160::repeat_byte(0xff))
+        .caller(H160::repeat_byte(0xaa))
+        .value(7u8);
+    // The code address differs from the context address in delegate calls.
+    handle.code_address = H160::repeat_byte(0xcc);
+
+    assert_eq!(
+        PrecompileHandleExt::caller(&handle),
+        H160::repeat_byte(0xaa)
+    );
+    assert_eq!(handle.apparent_value(), U256::from(7u8));
+    assert_eq!(
+        PrecompileHandleExt::address(&handle),
+        H160::repeat_byte(0xff)
+    );
+}
+
+#[test]
+fn revert_with_custom_error() {
+    let selector = function_selector!("InsufficientBalance(uint256,uint256)");
+
+    let failure = revert_custom_error(
+        selector.to_be_bytes(),
+        EvmDataWriter::new()
+            .write(U256::from(1u8))
+            .write(U256::from(2u8)),
+    );
+
+    let output = match failure {
+        PrecompileFailure::Revert {
+            exit_status: ExitRevert::Reverted,
+            output,
+        } => output,
+        other => panic!("expected a revert, got {:?}", other),
+    };
+
+    assert_eq!(output.len(), 4 + 2 * 32);
+    assert_eq!(EvmDataReader::peek_selector(&output), Ok(selector));
+
+    let mut reader = EvmDataReader::new_skip_selector(&output).expect("there is a selector");
+    assert_eq!(
+        reader.read::<(U256, U256)>(),
+        Ok((U256::from(1u8), U256::from(2u8)))
+    );
+    assert_eq!(revert::decode_revert_reason(&output), None);
+
+    // The selector can also be taken from the hash of the error signature.
+    let hash = keccak256!("InsufficientBalance(uint256,uint256)");
+    assert_eq!(
+        revert_custom_error(
+            [hash[0], hash[1], hash[2], hash[3]],
+            EvmDataWriter::new()
+                .write(U256::from(1u8))
+                .write(U256::from(2u8))
+        ),
+        revert(output)
+    );
+}
+
+#[test]
+fn reader_cursor_and_remaining_bytes() {
+    let input = EvmDataWriter::new()
+        .write(U256::from(1u8))
+        .write(U256::from(2u8))
+        .build();
+    let mut reader = EvmDataReader::new(&input);
+
+    assert_eq!(reader.cursor(), 0);
+    assert_eq!(reader.remaining_bytes(), 64);
+
+    reader.read::<U256>().expect("enough input");
+    assert_eq!(reader.cursor(), 32);
+    assert_eq!(reader.remaining_bytes(), 32);
+
+    reader.read::<U256>().expect("enough input");
+    assert_eq!(reader.remaining_bytes(), 0);
+
+    // A failed read moves the cursor past the end.
+    assert!(reader.read::<U256>().is_err());
+    assert_eq!(reader.remaining_bytes(), 0);
+}
+
+#[test]
+fn address_vec_conversions() {
+    let addresses: Vec<H160> = (1..=5).map(H160::repeat_byte).collect();
+
+    let output = EvmDataWriter::new()
+        .write(Address::from_h160_vec(addresses.clone()))
+        .build();
+    let decoded: Vec<Address> = EvmDataReader::new(&output).read().expect("valid array");
+
+    assert_eq!(decoded.len(), 5);
+    assert_eq!(Address::into_h160_vec(decoded), addresses);
+}
+
+#[test]
+fn address_vec_conversions_reuse_allocation() {
+    let mut addresses: Vec<H160> = Vec::with_capacity(8);
+    addresses.extend((1..=5).map(H160::repeat_byte));
+    let ptr = addresses.as_ptr() as usize;
+
+    let converted = Address::from_h160_vec(addresses);
+    assert_eq!(converted.as_ptr() as usize, ptr);
+    assert_eq!(converted.capacity(), 8);
+    assert_eq!(converted[4], Address(H160::repeat_byte(5)));
+
+    let converted = Address::into_h160_vec(converted);
+    assert_eq!(converted.as_ptr() as usize, ptr);
+    assert_eq!(converted.capacity(), 8);
+    assert_eq!(
+        converted,
+        (1..=5).map(H160::repeat_byte).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn keccak256_runtime() {
+    assert_eq!(
+        keccak256(b""),
+        H256(hex!(
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        ))
+    );
+    assert_eq!(
+        keccak256(b"transfer(address,uint256)"),
+        H256(keccak256!("transfer(address,uint256)"))
+    );
+}
+
+#[test]
+fn read_strict_bool() {
+    let input = EvmDataWriter::new()
+        .write(0u8)
+        .write(1u8)
+        .write(2u8)
+        .build();
+    let mut reader = EvmDataReader::new(&input);
+
+    assert_eq!(reader.read::<StrictBool>(), Ok(StrictBool(false)));
+    assert_eq!(reader.read::<StrictBool>(), Ok(StrictBool(true)));
+    assert_eq!(
+        reader.read::<StrictBool>(),
+        Err(revert_with_reason("invalid bool encoding"))
+    );
+
+    // bool stays lenient.
+    assert_eq!(EvmDataReader::new(&input[64..]).read::<bool>(), Ok(true));
+
+    let mut input = EvmDataWriter::new().write(1u8).build();
+    input[0] = 1;
+    assert_eq!(
+        EvmDataReader::new(&input).read::<StrictBool>(),
+        Err(revert_with_reason("invalid bool encoding"))
+    );
+}
+
+#[test]
+fn write_strict_bool() {
+    assert_eq!(
+        EvmDataWriter::new().write(StrictBool(true)).build(),
+        EvmDataWriter::new().write(true).build()
+    );
+    assert_eq!(
+        EvmDataWriter::new().write(StrictBool(false)).build(),
+        EvmDataWriter::new().write(false).build()
+    );
+}
+
+#[test]
+fn payable_address_is_encoded_as_address() {
+    let address = H160::repeat_byte(0x11);
+
+    let output = EvmDataWriter::new().write(PayableAddress(address)).build();
+    assert_eq!(output, EvmDataWriter::new().write(Address(address)).build());
+
+    let decoded: PayableAddress = EvmDataReader::new(&output).read().expect("valid address");
+    assert_eq!(H160::from(decoded), address);
+    assert_eq!(Address::from(decoded), Address(address));
+    assert_eq!(PayableAddress::from(Address(address)), decoded);
+}
+
+#[test]
+fn estimated_costs_match_recorded_costs() {
+    let logs = LogsBuilder::new(H160::repeat_byte(0xff));
+    let log1 = logs.log1(H256::repeat_byte(1), vec![0; 32]);
+    let log3 = logs.log3(
+        H256::repeat_byte(1),
+        H256::repeat_byte(2),
+        H256::repeat_byte(3),
+        vec![0; 70],
+    );
+
+    let mut handle = mock_handle();
+    handle
+        .record_log_costs_manual(2, 64)
+        .expect("not out of gas");
+    handle
+        .record_log_costs(&[&log1, &log3])
+        .expect("not out of gas");
+
+    let mut gasometer = Gasometer::new(&mut handle);
+    gasometer
+        .record_db_read::<mock::Runtime>()
+        .expect("not out of gas");
+    gasometer
+        .record_db_write::<mock::Runtime>()
+        .expect("not out of gas");
+
+    let estimated = estimate::log_costs_manual(2, 64)
+        + estimate::log_costs(&[&log1, &log3])
+        + estimate::db_read::<mock::Runtime>()
+        + estimate::db_write::<mock::Runtime>();
+
+    assert_eq!(estimated, handle.gas_used());
+    assert_eq!(
+        estimated,
+        375 + 2 * 375 + 8 * 64 + 1006 + (375 + 3 * 375 + 8 * 70) + 3_000
+    );
+}
+
+#[test]
+fn revert_reasons_are_accumulated() {
+    let mut reasons = revert::RevertReasons::new();
+    assert!(reasons.is_empty());
+
+    reasons.push_field("argument amount", RevertReason::value_is_too_large("value"));
+    reasons.push_field("argument to", RevertReason::custom("zero address"));
+
+    assert_eq!(
+        reasons.check(),
+        Err(revert_with_reason(
+            "argument amount: value is too large; argument to: zero address"
+        ))
+    );
+}
+
+#[test]
+fn revert_reasons_keep_order() {
+    let mut reasons = revert::RevertReasons::new();
+    reasons.push(RevertReason::custom("second"));
+    reasons.push(RevertReason::custom("first"));
+
+    assert_eq!(reasons.to_string(), "second; first");
+}
+
+#[test]
+fn empty_revert_reasons_dont_revert() {
+    assert_eq!(revert::RevertReasons::new().check(), Ok(()));
+}
+
+struct LengthHasher;
+
+impl KeccakHasher for LengthHasher {
+    fn keccak256(data: &[u8]) -> H256 {
+        H256::repeat_byte(data.len() as u8)
+    }
+}
+
+#[test]
+fn keccak256_with_custom_hasher() {
+    assert_eq!(
+        keccak256_with::<LengthHasher>(b"hello"),
+        H256::repeat_byte(5)
+    );
+    assert_eq!(
+        indexed_dynamic_with::<LengthHasher>(b"hi"),
+        H256::repeat_byte(2)
+    );
+    assert_eq!(keccak256_with::<SpIoKeccak>(b"hello"), keccak256(b"hello"));
+}
+
+#[test]
+fn read_selector_or_fallback() {
+    assert_eq!(
+        EvmDataReader::read_selector_or_fallback::<DispatchAction>(&[]),
+        Ok(Selector::Receive)
+    );
+    assert_eq!(
+        EvmDataReader::read_selector_or_fallback::<DispatchAction>(&[0x12, 0x34]),
+        Err(revert_with_reason("tried to parse selector out of bounds"))
+    );
+    assert_eq!(
+        EvmDataReader::read_selector_or_fallback::<DispatchAction>(&hex!("12345678")),
+        Ok(Selector::Fallback)
+    );
+    assert_eq!(
+        EvmDataReader::read_selector_or_fallback::<DispatchAction>(
+            &EvmDataWriter::new_with_selector(DispatchAction::Get).build()
+        ),
+        Ok(Selector::Known(DispatchAction::Get))
+    );
+}
+
+#[test]
+fn handle_read_selector_or_fallback() {
+    let handle = mock_handle().input(hex!("12345678aabb").to_vec());
+
+    assert_eq!(
+        handle.read_selector_or_fallback::<DispatchAction>(),
+        Ok(Selector::Fallback)
+    );
+}
+
+#[test]
+fn amount_from_tokens() {
+    assert_eq!(
+        Amount::from_tokens(3u8, 18),
+        Ok(Amount::from_wei(3_000_000_000_000_000_000u128))
+    );
+    assert_eq!(Amount::from_tokens(3u8, 0), Ok(Amount::from_wei(3u8)));
+    assert_eq!(Amount::from_wei(5u8).wei(), U256::from(5u8));
+}
+
+#[test]
+fn amount_overflow_reverts() {
+    assert_eq!(
+        Amount::from_tokens(U256::MAX, 1),
+        Err(revert_with_reason("amount overflow"))
+    );
+    assert_eq!(Amount::from_tokens(U256::MAX, 0), Ok(Amount(U256::MAX)));
+    // 10^78 doesn't fit in 256 bits.
+    assert_eq!(
+        Amount::from_tokens(1u8, 78),
+        Err(revert_with_reason("amount overflow"))
+    );
+
+    assert_eq!(
+        Amount(U256::MAX).checked_add(Amount::from_wei(1u8)),
+        Err(revert_with_reason("amount overflow"))
+    );
+    assert_eq!(
+        Amount::from_wei(1u8).checked_sub(Amount::from_wei(2u8)),
+        Err(revert_with_reason("amount underflow"))
+    );
+    assert_eq!(
+        Amount::from_wei(2u8).checked_sub(Amount::from_wei(1u8)),
+        Ok(Amount::from_wei(1u8))
+    );
+}
+
+#[test]
+fn amount_is_encoded_as_u256() {
+    let output = EvmDataWriter::new().write(Amount::from_wei(42u8)).build();
+
+    assert_eq!(output, EvmDataWriter::new().write(U256::from(42u8)).build());
+    assert_eq!(
+        EvmDataReader::new(&output).read::<Amount>(),
+        Ok(Amount::from_wei(42u8))
+    );
+}
+
+#[test]
+fn reader_skip() {
+    let input = EvmDataWriter::new()
+        .write(U256::from(1u8))
+        .write(U256::from(2u8))
+        .write(U256::from(3u8))
+        .build();
+    let mut reader = EvmDataReader::new(&input);
+
+    reader.skip(2).expect("enough input");
+    assert_eq!(reader.read::<U256>(), Ok(U256::from(3u8)));
+
+    let mut reader = EvmDataReader::new(&input);
+    reader.skip(0).expect("nothing to skip");
+    assert_eq!(reader.read::<U256>(), Ok(U256::from(1u8)));
+    assert_eq!(
+        reader.skip(3),
+        Err(revert_with_reason(
+            "Reading skipped words at offset 32 would read beyond input (len 96)"
+        ))
+    );
+}
+
+#[test]
+fn eip712_example_mail() {
+    // Example of the EIP-712 specification.
+    let domain_separator = eip712::domain_separator(
+        "Ether Mail",
+        "1",
+        1,
+        H160(hex!("CcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC")),
+    );
+    assert_eq!(
+        domain_separator,
+        H256(hex!(
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        ))
+    );
+
+    let person_type_hash = H256(keccak256!("Person(string name,address wallet)"));
+    let person = |name: &str, wallet: H160| {
+        eip712::hash_struct(
+            person_type_hash,
+            &EvmDataWriter::new()
+                .write(keccak256(name.as_bytes()))
+                .write(Address(wallet))
+                .build(),
+        )
+    };
+
+    let mail_type_hash = H256(keccak256!(
+        "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+    ));
+    let mail = eip712::hash_struct(
+        mail_type_hash,
+        &EvmDataWriter::new()
+            .write(person(
+                "Cow",
+                H160(hex!("CD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826")),
+            ))
+            .write(person(
+                "Bob",
+                H160(hex!("bBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB")),
+            ))
+            .write(keccak256(b"Hello, Bob!"))
+            .build(),
+    );
+    assert_eq!(
+        mail,
+        H256(hex!(
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        ))
+    );
+
+    assert_eq!(
+        eip712::digest(domain_separator, mail),
+        H256(hex!(
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        ))
+    );
+}
+
+// Signature of `keccak256("precompile-utils recover_signer")` by the private key
+// 0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318.
+const SIGNED_DIGEST: [u8; 32] =
+    hex!("bf3559d085a93d9522ef343464c4084ef61c13d99339cd070d57f59399ede352");
+const SIGNATURE_R: [u8; 32] =
+    hex!("94ccb3f370b92d55ebbd845f3edb47058cbb3218e58ac54d3beac714ba6c517b");
+const SIGNATURE_S: [u8; 32] =
+    hex!("76c4016963ba293971310a61a05e4eb5e1c6d93f257a6c835e54bf9025527644");
+const SIGNER: [u8; 20] = hex!("2c7536e3605d9c16a7a3d7b1898e529396a65c23");
+
+#[test]
+fn recover_signer_of_signature() {
+    assert_eq!(
+        SIGNED_DIGEST,
+        keccak256(b"precompile-utils recover_signer").0
+    );
+
+    assert_eq!(
+        recover_signer(
+            H256(SIGNED_DIGEST),
+            27,
+            H256(SIGNATURE_R),
+            H256(SIGNATURE_S)
+        ),
+        Ok(H160(SIGNER))
+    );
+
+    // Another recovery id recovers another address.
+    assert_ne!(
+        recover_signer(
+            H256(SIGNED_DIGEST),
+            28,
+            H256(SIGNATURE_R),
+            H256(SIGNATURE_S)
+        ),
+        Ok(H160(SIGNER))
+    );
+}
+
+#[test]
+fn recover_signer_rejects_malleable_signature() {
+    // Same signature with `s` replaced by `n - s`, which is also valid without EIP-2.
+    let high_s = H256(hex!(
+        "893bfe969c45d6c68ecef59e5fa1b148d8e803a789ce33b8617d9efcaae3cafd"
+    ));
+
+    assert_eq!(
+        recover_signer(H256(SIGNED_DIGEST), 28, H256(SIGNATURE_R), high_s),
+        Err(revert_with_reason("invalid signature"))
+    );
+}
+
+#[test]
+fn recover_signer_rejects_invalid_signature() {
+    assert_eq!(
+        recover_signer(H256(SIGNED_DIGEST), 1, H256(SIGNATURE_R), H256(SIGNATURE_S)),
+        Err(revert_with_reason("invalid signature"))
+    );
+    assert_eq!(
+        recover_signer(H256(SIGNED_DIGEST), 27, H256::zero(), H256::zero()),
+        Err(revert_with_reason("invalid signature"))
+    );
+}
+
+#[test]
+fn parse_checksummed_address_checks_checksum() {
+    // Test vector of EIP-55.
+    let address = H160(hex!("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"));
+
+    assert_eq!(
+        parse_checksummed_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+        Ok(address)
+    );
+    assert_eq!(
+        parse_checksummed_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"),
+        Ok(address)
+    );
+    assert_eq!(
+        parse_checksummed_address("0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+        Err(revert_with_reason("invalid address checksum"))
+    );
+}
+
+#[test]
+fn parse_checksummed_address_rejects_malformed_input() {
+    assert_eq!(
+        parse_checksummed_address("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"),
+        Err(revert_with_reason("address must start with 0x"))
+    );
+    assert_eq!(
+        parse_checksummed_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea"),
+        Err(revert_with_reason("address must have 40 hex digits"))
+    );
+    assert_eq!(
+        parse_checksummed_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beagg"),
+        Err(revert_with_reason("invalid hex digit in address"))
+    );
+}
+
+#[test]
+fn read_array_capped() {
+    let input = EvmDataWriter::new()
+        .write(vec![U256::from(1u8), U256::from(2u8)])
+        .build();
+
+    assert_eq!(
+        EvmDataReader::new(&input).read_array_capped::<U256>(2),
+        Ok(vec![U256::from(1u8), U256::from(2u8)])
+    );
+    assert_eq!(
+        EvmDataReader::new(&input).read_array_capped::<U256>(1),
+        Err(revert_with_reason("array has more than 1 items"))
+    );
+}
+
+#[test]
+fn read_address_amount_pairs() {
+    let alice = H160::repeat_byte(0x11);
+    let bob = H160::repeat_byte(0x22);
+
+    let input = EvmDataWriter::new()
+        .write(vec![Address(alice), Address(bob)])
+        .write(vec![U256::from(100u8), U256::from(200u8)])
+        .build();
+    assert_eq!(
+        EvmDataReader::new(&input).read_address_amount_pairs(2),
+        Ok(vec![(alice, U256::from(100u8)), (bob, U256::from(200u8))])
+    );
+    assert_eq!(
+        EvmDataReader::new(&input).read_address_amount_pairs(1),
+        Err(revert_with_reason("array has more than 1 items"))
+    );
+
+    let input = EvmDataWriter::new()
+        .write(Vec::<Address>::new())
+        .write(Vec::<U256>::new())
+        .build();
+    assert_eq!(
+        EvmDataReader::new(&input).read_address_amount_pairs(0),
+        Ok(vec![])
+    );
+
+    let input = EvmDataWriter::new()
+        .write(vec![Address(alice), Address(bob)])
+        .write(vec![U256::from(100u8)])
+        .build();
+    assert_eq!(
+        EvmDataReader::new(&input).read_address_amount_pairs(2),
+        Err(revert_with_reason("mismatched array lengths"))
+    );
+}
+
+#[test]
+fn read_array_capped_rejects_huge_length_before_allocating() {
+    let input = EvmDataWriter::new()
+        .write(U256::from(0x20))
+        .write(U256::from(1_000_000_000u64))
+        .build();
+
+    assert_eq!(
+        EvmDataReader::new(&input).read_array_capped::<U256>(100),
+        Err(revert_with_reason("array has more than 100 items"))
+    );
+}
+
+#[test]
+fn option_round_trip() {
+    let some = EvmDataWriter::new().write(Some(U256::from(42u8))).build();
+    assert_eq!(
+        some,
+        EvmDataWriter::new()
+            .write(true)
+            .write(U256::from(42u8))
+            .build()
+    );
+    assert_eq!(
+        EvmDataReader::new(&some).read::<Option<U256>>(),
+        Ok(Some(U256::from(42u8)))
+    );
+
+    let none = EvmDataWriter::new().write(None::<U256>).build();
+    assert_eq!(none, vec![0u8; 64]);
+    assert_eq!(EvmDataReader::new(&none).read::<Option<U256>>(), Ok(None));
+}
+
+#[test]
+fn option_of_dynamic_type_round_trip() {
+    let some = EvmDataWriter::new()
+        .write(Some(Bytes::from("value")))
+        .build();
+    assert_eq!(
+        EvmDataReader::new(&some).read::<Option<Bytes>>(),
+        Ok(Some(Bytes::from("value")))
+    );
+
+    let none = EvmDataWriter::new().write(None::<Bytes>).build();
+    assert_eq!(EvmDataReader::new(&none).read::<Option<Bytes>>(), Ok(None));
+}
+
+#[test]
+fn deposit_value_credits_destination() {
+    mock::new_test_ext().execute_with(|| {
+        let precompile = H160::repeat_byte(0xff);
+        let dest = H160::repeat_byte(0x11);
+        // Value credited by the EVM before executing the precompile.
+        mock::Balances::make_free_balance_be(&precompile, 100);
+
+        let mut handle = mock_handle().value(100u8);
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::deposit_value(&mut handle, dest),
+            Ok(())
+        );
+
+        assert_eq!(mock::Balances::free_balance(dest), 100);
+        assert_eq!(mock::Balances::free_balance(precompile), 0);
+        assert_eq!(handle.gas_used(), 2 * 1_000 + 2 * 2_000);
+    })
+}
+
+#[test]
+fn deposit_value_reverts_if_transfer_fails() {
+    mock::new_test_ext().execute_with(|| {
+        let precompile = H160::repeat_byte(0xff);
+        let dest = H160::repeat_byte(0x11);
+        mock::Balances::make_free_balance_be(&precompile, 50);
+
+        let mut handle = mock_handle().value(100u8);
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::deposit_value(&mut handle, dest),
+            Err(revert_with_reason("failed to transfer value"))
+        );
+
+        assert_eq!(mock::Balances::free_balance(dest), 0);
+        assert_eq!(mock::Balances::free_balance(precompile), 50);
+    })
+}
+
+#[test]
+fn deposit_without_value_does_nothing() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::deposit_value(&mut handle, H160::repeat_byte(0x11)),
+            Ok(())
+        );
+        assert_eq!(handle.gas_used(), 0);
+    })
+}
+
+#[test]
+fn read_packed_address_uint8_bytes32() {
+    // abi.encodePacked(address, uint8, bytes32)
+    let mut input = Vec::new();
+    input.extend_from_slice(H160::repeat_byte(0x11).as_bytes());
+    input.push(42);
+    input.extend_from_slice(H256::repeat_byte(0x22).as_bytes());
+    assert_eq!(input.len(), 53);
+
+    let mut reader = packed::PackedReader::new(&input);
+    assert_eq!(
+        reader.read::<(Address, u8, H256)>(),
+        Ok((
+            Address(H160::repeat_byte(0x11)),
+            42,
+            H256::repeat_byte(0x22)
+        ))
+    );
+    assert_eq!(reader.expect_end(), Ok(()));
+
+    let mut reader = packed::PackedReader::new(&input[..52]);
+    assert_eq!(
+        reader.read::<(Address, u8, H256)>(),
+        Err(revert_with_reason(
+            "Reading bytes32 at offset 21 would read beyond input (len 52)"
+        ))
+    );
+}
+
+#[test]
+fn read_packed_trailing_bytes() {
+    // abi.encodePacked(uint16, bytes)
+    let input = hex!("0102deadbeef");
+
+    let mut reader = packed::PackedReader::new(&input);
+    assert_eq!(reader.read::<u16>(), Ok(0x0102));
+    assert_eq!(
+        reader.expect_end(),
+        Err(revert_with_reason("unexpected trailing 4 bytes"))
+    );
+    assert_eq!(reader.read::<Bytes>(), Ok(Bytes(hex!("deadbeef").to_vec())));
+    assert_eq!(reader.expect_end(), Ok(()));
+
+    // Nothing can be read after the dynamic value.
+    assert_eq!(
+        reader.read::<u8>(),
+        Err(revert_with_reason(
+            "Reading u8 at offset 6 would read beyond input (len 6)"
+        ))
+    );
+}
+
+#[test]
+fn dispatch_gas_cost_matches_recorded_gas() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+        let call = weighted_call(1_234, 0, None);
+
+        let quote = RuntimeHelper::<mock::Runtime>::dispatch_gas_cost(&call);
+        assert_eq!(quote, 1_234);
+
+        RuntimeHelper::<mock::Runtime>::try_dispatch(
+            &mut handle,
+            mock::RuntimeOrigin::root(),
+            call,
+        )
+        .expect("enough gas");
+
+        assert_eq!(handle.gas_used, quote);
+    })
+}
+
+#[test]
+fn try_dispatch_with_cached_dispatch_info_matches_try_dispatch() {
+    mock::new_test_ext().execute_with(|| {
+        let call = weighted_call(1_000, 0, Some((600, 0)));
+        let dispatch_info = call.get_dispatch_info();
+
+        let mut default_handle = mock_handle();
+        let mut cached_handle = mock_handle();
+        for _ in 0..3 {
+            RuntimeHelper::<mock::Runtime>::try_dispatch(
+                &mut default_handle,
+                mock::RuntimeOrigin::root(),
+                call.clone(),
+            )
+            .expect("enough gas");
+
+            RuntimeHelper::<mock::Runtime>::try_dispatch_with_dispatch_info(
+                &mut cached_handle,
+                mock::RuntimeOrigin::root(),
+                call.clone(),
+                dispatch_info,
+            )
+            .expect("enough gas");
+        }
+
+        // The actual weight is recorded in both cases.
+        assert_eq!(default_handle.gas_used, 1_800);
+        assert_eq!(cached_handle.gas_used, default_handle.gas_used);
+
+        // The required gas comes from the cached info.
+        let mut handle = mock_handle();
+        handle.gas_limit = 999;
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::try_dispatch_with_dispatch_info(
+                &mut handle,
+                mock::RuntimeOrigin::root(),
+                call,
+                dispatch_info,
+            ),
+            Err(PrecompileFailure::Error {
+                exit_status: ExitError::OutOfGas
+            })
+        );
+    })
+}
+
+#[test]
+fn dispatch_pays_fee_matches_declared_pays() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+        let call = mock::pallet_mock_calls::Call::<mock::Runtime>::free { ref_time: 1_000 };
+
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::dispatch_pays_fee(&call),
+            Pays::No
+        );
+        assert_eq!(
+            RuntimeHelper::<mock::Runtime>::dispatch_pays_fee(&weighted_call(1_000, 0, None)),
+            Pays::Yes
+        );
+
+        let info = call.get_dispatch_info();
+        let post_info = RuntimeHelper::<mock::Runtime>::try_dispatch_with_info(
+            &mut handle,
+            mock::RuntimeOrigin::root(),
+            call,
+        )
+        .expect("enough gas");
+        assert_eq!(post_info.pays_fee(&info), Pays::No);
+    })
+}
+
+#[test]
+fn log_builder_matches_fixed_arity_logs() {
+    let logs = LogsBuilder::new(H160::repeat_byte(0xff));
+    let topics: Vec<_> = (1..=4).map(H256::repeat_byte).collect();
+
+    assert_eq!(logs.log().data(vec![1, 2]).build(), logs.log0(vec![1, 2]));
+    assert_eq!(
+        logs.log()
+            .topic(topics[0])
+            .and_then(|log| log.topic(topics[1]))
+            .map(|log| log.data(vec![1, 2]).build()),
+        Ok(logs.log2(topics[0], topics[1], vec![1, 2]))
+    );
+
+    let mut log = logs.log();
+    for topic in &topics {
+        log = log.topic(*topic).expect("at most 4 topics");
+    }
+    assert_eq!(
+        log.build(),
+        logs.log4(topics[0], topics[1], topics[2], topics[3], vec![])
+    );
+}
+
+#[test]
+fn log_builder_rejects_fifth_topic() {
+    let mut log = LogsBuilder::new(H160::repeat_byte(0xff)).log();
+    for i in 1..=4 {
+        log = log.topic(H256::repeat_byte(i)).expect("at most 4 topics");
+    }
+
+    assert_eq!(
+        log.topic(H256::repeat_byte(5)).map(LogBuilder::build),
+        Err(revert_with_reason("log can't have more than 4 topics"))
+    );
+}
+
+#[test]
+fn scale_native_balance_to_evm() {
+    use decimals::{scale_to_evm, PrecisionLoss};
+
+    assert_eq!(
+        scale_to_evm(1_500_000_000_000, 12, 18, PrecisionLoss::Reject),
+        Ok(U256::from(1_500_000_000_000_000_000u128))
+    );
+    assert_eq!(
+        scale_to_evm(u128::MAX, 12, 18, PrecisionLoss::Reject),
+        Ok(U256::from(u128::MAX) * U256::from(1_000_000))
+    );
+    assert_eq!(
+        scale_to_evm(1, 0, 78, PrecisionLoss::Reject),
+        Err(revert_with_reason("amount overflow"))
+    );
+    assert_eq!(
+        scale_to_evm(0, 0, 78, PrecisionLoss::Reject),
+        Ok(U256::zero())
+    );
+}
+
+#[test]
+fn scale_evm_balance_to_native() {
+    use decimals::{scale_from_evm, PrecisionLoss};
+
+    assert_eq!(
+        scale_from_evm(
+            U256::from(1_500_000_000_000_000_000u128),
+            12,
+            18,
+            PrecisionLoss::Reject
+        ),
+        Ok(1_500_000_000_000)
+    );
+
+    // 1.000000000000000001 can't be represented with 12 decimals.
+    let lossy = U256::from(1_000_000_000_000_000_001u128);
+    assert_eq!(
+        scale_from_evm(lossy, 12, 18, PrecisionLoss::Reject),
+        Err(revert_with_reason("amount precision loss"))
+    );
+    assert_eq!(
+        scale_from_evm(lossy, 12, 18, PrecisionLoss::Truncate),
+        Ok(1_000_000_000_000)
+    );
+
+    assert_eq!(
+        scale_from_evm(U256::MAX, 12, 18, PrecisionLoss::Truncate),
+        Err(revert_with_reason("amount overflow"))
+    );
+}
+
+#[test]
+fn memory_expansion_cost_follows_evm_formula() {
+    assert_eq!(memory_expansion_cost(0, 0), 0);
+    assert_eq!(memory_expansion_cost(0, 1), 3);
+    assert_eq!(memory_expansion_cost(0, 32), 98);
+    assert_eq!(memory_expansion_cost(0, 1024), 5_120);
+    assert_eq!(memory_expansion_cost(32, 1024), 5_022);
+
+    // Shrinking is free.
+    assert_eq!(memory_expansion_cost(1024, 32), 0);
+
+    // The quadratic term overflows.
+    assert_eq!(memory_expansion_cost(0, u64::MAX / 2), u64::MAX);
+}
+
+#[test]
+fn record_memory_expansion() {
+    let mut handle = mock_handle();
+
+    handle
+        .record_memory_expansion(32, 1024)
+        .expect("enough gas");
+    assert_eq!(handle.gas_used, 5_022);
+
+    assert_eq!(
+        handle.record_memory_expansion(0, u64::MAX / 2),
+        Err(PrecompileFailure::Error {
+            exit_status: ExitError::OutOfGas
+        })
+    );
+}
+
+/// Mapping charging half the gas of the runtime mapping.
+struct HalfGasWeightMapping;
+
+impl GasWeightMapping for HalfGasWeightMapping {
+    fn gas_to_weight(gas: u64, without_base_weight: bool) -> Weight {
+        <mock::Runtime as pallet_evm::Config>::GasWeightMapping::gas_to_weight(
+            gas.saturating_mul(2),
+            without_base_weight,
+        )
+    }
+
+    fn weight_to_gas(weight: Weight) -> u64 {
+        <mock::Runtime as pallet_evm::Config>::GasWeightMapping::weight_to_gas(weight) / 2
+    }
+}
+
+#[test]
+fn try_dispatch_with_mapping_records_mapped_gas() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+
+        RuntimeHelper::<mock::Runtime>::try_dispatch_with_mapping::<HalfGasWeightMapping, _>(
+            &mut handle,
+            mock::RuntimeOrigin::root(),
+            weighted_call(1_000, 0, Some((800, 0))),
+        )
+        .expect("enough gas");
+        assert_eq!(handle.gas_used, 400);
+
+        // The default mapping is unchanged.
+        RuntimeHelper::<mock::Runtime>::try_dispatch(
+            &mut handle,
+            mock::RuntimeOrigin::root(),
+            weighted_call(1_000, 0, Some((800, 0))),
+        )
+        .expect("enough gas");
+        assert_eq!(handle.gas_used, 1_200);
+    })
+}
+
+#[test]
+fn scaled_mul_div_keeps_scale() {
+    let e18 = U256::exp10(18);
+    let one_and_half = Scaled::<18>::from_raw(e18 * 3 / 2);
+    let two_and_half = Scaled::<18>::from_raw(e18 * 5 / 2);
+
+    // 1.5 * 2.5 = 3.75
+    assert_eq!(
+        one_and_half.checked_mul(two_and_half),
+        Ok(Scaled::from_raw(e18 * 375 / 100))
+    );
+    // 1.5 / 2.5 = 0.6
+    assert_eq!(
+        one_and_half.checked_div(two_and_half),
+        Ok(Scaled::from_raw(e18 * 6 / 10))
+    );
+    // 1.5 * 2.5 / 1.5 = 2.5, the intermediate product not fitting in an 18 decimals scale.
+    assert_eq!(
+        one_and_half.mul_div(two_and_half, one_and_half),
+        Ok(two_and_half)
+    );
+
+    // The 512 bits intermediate product doesn't overflow.
+    let max = Scaled::<18>::from_raw(U256::MAX);
+    assert_eq!(max.mul_div(max, max), Ok(max));
+    assert_eq!(
+        max.checked_mul(two_and_half),
+        Err(revert_with_reason("scaled value overflow"))
+    );
+    assert_eq!(
+        one_and_half.checked_div(Scaled::default()),
+        Err(revert_with_reason("division by zero"))
+    );
+}
+
+#[test]
+fn scaled_is_encoded_as_u256() {
+    let value = Scaled::<18>::from_raw(U256::exp10(18));
+    let output = EvmDataWriter::new().write(value).build();
+
+    assert_eq!(output, EvmDataWriter::new().write(U256::exp10(18)).build());
+    assert_eq!(EvmDataReader::new(&output).read::<Scaled<18>>(), Ok(value));
+}
+
+#[test]
+fn static_context_only_allows_views() {
+    let (result, _) = testing::MockHandle::default()
+        .static_call(true)
+        .run::<DispatchPrecompile>(EvmDataWriter::new_with_selector(DispatchAction::Get).build());
+    assert_eq!(
+        result,
+        Ok(succeed(EvmDataWriter::new().write(U256::from(1u8)).build()))
+    );
+
+    testing::assert_static_rejected::<DispatchPrecompile>(
+        EvmDataWriter::new_with_selector(DispatchAction::Set)
+            .write(U256::from(42u8))
+            .build(),
+    );
+    testing::assert_static_rejected::<DispatchPrecompile>(
+        EvmDataWriter::new_with_selector(DispatchAction::Deposit).build(),
+    );
+}
+
+#[test]
+fn dispatch_routes_aliases_to_canonical_function() {
+    let old_selector = function_selector!("store(uint256)");
+    let input = EvmDataWriter::new_with_selector(old_selector)
+        .write(U256::from(42u8))
+        .build();
+
+    assert_eq!(
+        EvmDataReader::read_selector::<DispatchAction>(&input),
+        Ok(DispatchAction::Set)
+    );
+    assert_eq!(
+        u32::from(DispatchAction::Set),
+        function_selector!("set(uint256)")
+    );
+
+    let mut handle = mock_handle();
+    let (result, gas_used) = handle.run::<DispatchPrecompile>(input);
+    assert_eq!(
+        result,
+        Ok(succeed(
+            EvmDataWriter::new().write(U256::from(42u8)).build()
+        ))
+    );
+    assert_eq!(gas_used, 200);
+}
+
+// ABI encoding of `((uint256, bytes), bytes[])` `((1, 0xdeadbeef), [0x1234, 0xaa * 33])`, as
+// produced by ethers.js.
+const ETHERS_TAGGED_BYTES_AND_BYTES_ARRAY: [u8; 0x1c0] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000000040
+	00000000000000000000000000000000000000000000000000000000000000c0
+	0000000000000000000000000000000000000000000000000000000000000001
+	0000000000000000000000000000000000000000000000000000000000000040
+	0000000000000000000000000000000000000000000000000000000000000004
+	deadbeef00000000000000000000000000000000000000000000000000000000
+	0000000000000000000000000000000000000000000000000000000000000002
+	0000000000000000000000000000000000000000000000000000000000000040
+	0000000000000000000000000000000000000000000000000000000000000080
+	0000000000000000000000000000000000000000000000000000000000000002
+	1234000000000000000000000000000000000000000000000000000000000000
+	0000000000000000000000000000000000000000000000000000000000000021
+	aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+	aa00000000000000000000000000000000000000000000000000000000000000"
+);
+
+#[derive(Clone, Debug, PartialEq, Eq, EvmData)]
+struct Tagged {
+    tag: U256,
+    data: Bytes,
+}
+
+#[test]
+fn read_nested_bytes_relative_to_enclosing_region() {
+    let mut reader = EvmDataReader::new(&ETHERS_TAGGED_BYTES_AND_BYTES_ARRAY);
+
+    // The offset of `data` is relative to the start of the struct, and the offsets of the
+    // array items are relative to the start of the items.
+    let tagged: Tagged = reader.read().expect("to correctly parse struct");
+    let array: Vec<Bytes> = reader.read().expect("to correctly parse bytes[]");
+    reader.expect_end().expect("no trailing data");
+
+    assert_eq!(
+        tagged,
+        Tagged {
+            tag: U256::one(),
+            data: Bytes(hex!("deadbeef").to_vec()),
+        }
+    );
+    assert_eq!(
+        array,
+        vec![Bytes(hex!("1234").to_vec()), Bytes(vec![0xaa; 33])]
+    );
+
+    assert_eq!(
+        EvmDataWriter::new().write(tagged).write(array).build(),
+        ETHERS_TAGGED_BYTES_AND_BYTES_ARRAY
+    );
+}
+
+#[test]
+fn require_value_checks_exact_payment() {
+    let price = U256::from(1_000u32);
+
+    assert_eq!(mock_handle().value(price).require_value(price), Ok(()));
+    testing::assert_revert_eq(
+        mock_handle().value(999u32).require_value(price),
+        "incorrect payment: expected 1000 got 999",
+    );
+    testing::assert_revert_eq(
+        mock_handle().value(1_001u32).require_value(price),
+        "incorrect payment: expected 1000 got 1001",
+    );
+}
+
+#[test]
+fn require_no_value() {
+    assert_eq!(mock_handle().require_no_value(), Ok(()));
+    testing::assert_revert_eq(
+        mock_handle().value(1u32).require_no_value(),
+        "function is not payable",
+    );
+}
+
+#[test]
+fn require_no_value_reverts_with_reason() {
+    assert_eq!(
+        mock_handle().value(U256::MAX).require_no_value(),
+        Err(revert_with_reason("function is not payable"))
+    );
+    assert_eq!(
+        mock_handle()
+            .static_call(true)
+            .value(1u32)
+            .require_no_value(),
+        Err(revert_with_reason("function is not payable"))
+    );
+}
+
+#[test]
+fn require_caller_checks_allowlist() {
+    let router = H160::repeat_byte(0xaa);
+    let other = H160::repeat_byte(0xbb);
+
+    assert_eq!(mock_handle().require_caller(&[other, router]), Ok(()));
+    testing::assert_revert_eq(
+        mock_handle().require_caller(&[other]),
+        "caller not allowed: 0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+    );
+    testing::assert_revert_eq(
+        mock_handle().require_caller(&[]),
+        "caller not allowed: 0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+    );
+}
+
+#[generate_function_selector]
+#[derive(Debug, PartialEq)]
+enum AuditedAction {
+    #[view]
+    BalanceOf = "balanceOf(address)",
+    #[alias("send(address,uint256)")]
+    Transfer = "transfer(address,uint256)",
+    #[payable]
+    Deposit = "deposit()",
+}
+
+#[test]
+fn selectors_table_lists_modifiers() {
+    assert_eq!(
+        AuditedAction::SELECTORS,
+        &[
+            (0x70a08231, "balanceOf(address)", FunctionModifier::View),
+            (
+                0xa9059cbb,
+                "transfer(address,uint256)",
+                FunctionModifier::NonPayable
+            ),
+            (
+                function_selector!("send(address,uint256)"),
+                "send(address,uint256)",
+                FunctionModifier::NonPayable
+            ),
+            (
+                function_selector!("deposit()"),
+                "deposit()",
+                FunctionModifier::Payable
+            ),
+        ]
+    );
+
+    assert_eq!(AuditedAction::BalanceOf.modifier(), FunctionModifier::View);
+    assert_eq!(
+        AuditedAction::Transfer.modifier(),
+        FunctionModifier::NonPayable
+    );
+    assert_eq!(AuditedAction::Deposit.modifier(), FunctionModifier::Payable);
+}
+
+#[test]
+fn function_modifier_from_state_mutability() {
+    assert_eq!(
+        FunctionModifier::from_state_mutability("view"),
+        Some(FunctionModifier::View)
+    );
+    assert_eq!(
+        FunctionModifier::from_state_mutability("pure"),
+        Some(FunctionModifier::View)
+    );
+    assert_eq!(
+        FunctionModifier::from_state_mutability("nonpayable"),
+        Some(FunctionModifier::NonPayable)
+    );
+    assert_eq!(
+        FunctionModifier::from_state_mutability("payable"),
+        Some(FunctionModifier::Payable)
+    );
+    assert_eq!(FunctionModifier::from_state_mutability("constant"), None);
+}
+
+#[test]
+fn record_log_costs_is_atomic() {
+    let logs = LogsBuilder::new(H160::repeat_byte(0xff));
+    let log1 = logs.log1(H256::repeat_byte(1), vec![]);
+    let log2 = logs.log2(H256::repeat_byte(1), H256::repeat_byte(2), vec![0; 32]);
+
+    // The first log (750) is affordable but not both (750 + 1381).
+    let mut handle = testing::MockHandle::default().gas_limit(1_000);
+    assert_eq!(
+        handle.record_log_costs(&[&log1, &log2]),
+        Err(PrecompileFailure::Error {
+            exit_status: ExitError::OutOfGas
+        })
+    );
+    assert_eq!(handle.gas_used(), 0);
+    assert!(handle.logs().is_empty());
+
+    let mut handle = testing::MockHandle::default().gas_limit(2_131);
+    handle
+        .record_log_costs(&[&log1, &log2])
+        .expect("enough gas for both logs");
+    assert_eq!(handle.gas_used(), 2_131);
+}
+
+/// Schedule with cheaper topics and more expensive data than Ethereum.
+struct CustomLogCostSchedule;
+
+impl LogCostSchedule for CustomLogCostSchedule {
+    const G_LOGDATA: u64 = 16;
+    const G_LOGTOPIC: u64 = 100;
+}
+
+#[test]
+fn log_costs_with_custom_schedule() {
+    assert_eq!(
+        log_costs_with::<EthereumLogCostSchedule>(2, 32),
+        log_costs(2, 32)
+    );
+    assert_eq!(
+        log_costs_with::<CustomLogCostSchedule>(2, 32),
+        375 + 2 * 100 + 32 * 16
+    );
+    assert_eq!(
+        log_costs_checked_with::<CustomLogCostSchedule>(2, 32),
+        Ok(1_087)
+    );
+
+    let logs = LogsBuilder::new(H160::repeat_byte(0xff));
+    let log1 = logs.log1(H256::repeat_byte(1), vec![]);
+    let log2 = logs.log2(H256::repeat_byte(1), H256::repeat_byte(2), vec![0; 32]);
+
+    let mut handle = testing::MockHandle::default();
+    handle
+        .record_log_costs_with::<CustomLogCostSchedule>(&[&log1, &log2])
+        .expect("enough gas");
+    handle
+        .record_log_costs_manual_with::<CustomLogCostSchedule>(0, 1)
+        .expect("enough gas");
+    assert_eq!(handle.gas_used(), 475 + 1_087 + 391);
+}
+
+#[test]
+fn function_round_trip() {
+    let function = Function::new(H160::repeat_byte(0x11), 0xa9059cbb);
+    let output = EvmDataWriter::new().write(function).build();
+
+    assert_eq!(
+        output,
+        hex!("1111111111111111111111111111111111111111a9059cbb0000000000000000")
+    );
+    assert_eq!(EvmDataReader::new(&output).read::<Function>(), Ok(function));
+}
+
+#[test]
+fn function_rejects_dirty_padding() {
+    let mut input = EvmDataWriter::new()
+        .write(Function::new(H160::repeat_byte(0x11), 0xa9059cbb))
+        .build();
+    input[31] = 1;
+
+    assert_eq!(
+        EvmDataReader::new(&input).read::<Function>(),
+        Err(revert_with_reason("invalid function encoding"))
+    );
+}
+
+fn subcall_target_gas(gas_limit: Option<u64>) -> Option<u64> {
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    let target_gas = Rc::new(Cell::new(None));
+    let subcall_target_gas = target_gas.clone();
+
+    let mut handle = testing::MockHandle::default()
+        .gas_limit(64_000)
+        .subcall_handle(move |subcall: testing::Subcall| {
+            subcall_target_gas.set(subcall.target_gas);
+
+            testing::SubcallOutput {
+                reason: ExitReason::Succeed(ExitSucceed::Returned),
+                output: vec![],
+                cost: 0,
+                logs: vec![],
+            }
+        });
+
+    subcall::call(
+        &mut handle,
+        H160::repeat_byte(0x11),
+        vec![],
+        U256::zero(),
+        gas_limit,
+    )
+    .expect("subcall succeeds");
+
+    target_gas.get()
+}
+
+#[test]
+fn subcall_clamps_gas_limit() {
+    // 64_000 - 64_000 / 64
+    assert_eq!(subcall_target_gas(Some(100_000)), Some(63_000));
+    assert_eq!(subcall_target_gas(Some(63_001)), Some(63_000));
+    assert_eq!(subcall_target_gas(Some(10_000)), Some(10_000));
+}
+
+#[test]
+fn subcall_forwards_clamped_gas_without_limit() {
+    assert_eq!(subcall_target_gas(None), Some(63_000));
+}
+
+#[test]
+fn h160_is_encoded_as_address() {
+    let value = H160::repeat_byte(0x11);
+    let output = EvmDataWriter::new().write(value).build();
+
+    assert_eq!(output, EvmDataWriter::new().write(Address(value)).build());
+    assert_eq!(&output[..12], &[0u8; 12]);
+    assert_eq!(EvmDataReader::new(&output).read::<H160>(), Ok(value));
+    assert_eq!(
+        EvmDataReader::new(&output).read::<Address>(),
+        Ok(Address(value))
+    );
+}
+
+#[test]
+fn h256_round_trip() {
+    let value = H256::repeat_byte(0x22);
+    let output = EvmDataWriter::new().write(value).build();
+
+    assert_eq!(output, value.as_bytes());
+    assert_eq!(EvmDataReader::new(&output).read::<H256>(), Ok(value));
+}
+
+#[test]
+fn handle_unknown_selector_reports_selector() {
+    let input = EvmDataWriter::new_with_selector(0xdeadbeefu32)
+        .write(U256::one())
+        .build();
+    testing::assert_revert_eq(
+        Err::<(), _>(handle_unknown_selector(&input)),
+        "unknown selector 0xdeadbeef",
+    );
+
+    testing::assert_revert_eq(
+        Err::<(), _>(handle_unknown_selector(&[0x01, 0x02])),
+        "unknown selector 0x0102",
+    );
+}
+
+// ABI encoding of `string[]` `[]`, as produced by ethers.js.
+const ETHERS_EMPTY_STRING_ARRAY: [u8; 0x40] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000000020
+	0000000000000000000000000000000000000000000000000000000000000000"
+);
+
+// ABI encoding of `string[]` `["hello"]`, as produced by ethers.js.
+const ETHERS_STRING_ARRAY: [u8; 0xa0] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000000020
+	0000000000000000000000000000000000000000000000000000000000000001
+	0000000000000000000000000000000000000000000000000000000000000020
+	0000000000000000000000000000000000000000000000000000000000000005
+	68656c6c6f000000000000000000000000000000000000000000000000000000"
+);
+
+// ABI encoding of `string[]` `["", "a", "The quick brown fox jumps over the lazy dog"]`, as produced by ethers.js.
+const ETHERS_MIXED_STRING_ARRAY: [u8; 0x160] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000000020
+	0000000000000000000000000000000000000000000000000000000000000003
+	0000000000000000000000000000000000000000000000000000000000000060
+	0000000000000000000000000000000000000000000000000000000000000080
+	00000000000000000000000000000000000000000000000000000000000000c0
+	0000000000000000000000000000000000000000000000000000000000000000
+	0000000000000000000000000000000000000000000000000000000000000001
+	6100000000000000000000000000000000000000000000000000000000000000
+	000000000000000000000000000000000000000000000000000000000000002b
+	54686520717569636b2062726f776e20666f78206a756d7073206f7665722074
+	6865206c617a7920646f67000000000000000000000000000000000000000000"
+);
+
+#[test]
+fn string_arrays_match_ethers() {
+    let cases: [(&[u8], Vec<&str>); 3] = [
+        (&ETHERS_EMPTY_STRING_ARRAY, vec![]),
+        (&ETHERS_STRING_ARRAY, vec!["hello"]),
+        (
+            &ETHERS_MIXED_STRING_ARRAY,
+            vec!["", "a", "The quick brown fox jumps over the lazy dog"],
+        ),
+    ];
+
+    for (encoded, strings) in cases {
+        let array: Vec<String> = strings.iter().map(|s| s.to_string()).collect();
+
+        let writer_output = EvmDataWriter::new().write(array.clone()).build();
+        assert_eq!(writer_output, encoded);
+
+        let mut reader = EvmDataReader::new(encoded);
+        let parsed: Vec<String> = reader.read().expect("to correctly parse Vec<String>");
+        reader.expect_end().expect("no trailing data");
+        assert_eq!(parsed, array);
+    }
+}
+
+#[test]
+fn bytes_arrays_are_encoded_like_string_arrays() {
+    let array: Vec<Bytes> = vec![
+        Bytes::from(""),
+        Bytes::from("a"),
+        Bytes::from("The quick brown fox jumps over the lazy dog"),
+    ];
+
+    let writer_output = EvmDataWriter::new().write(array.clone()).build();
+    assert_eq!(writer_output, ETHERS_MIXED_STRING_ARRAY);
+
+    let parsed: Vec<Bytes> = EvmDataReader::new(&ETHERS_MIXED_STRING_ARRAY)
+        .read()
+        .expect("to correctly parse Vec<Bytes>");
+    assert_eq!(parsed, array);
+}
+
+#[test]
+fn calibration_handle_sums_costs() {
+    let context = Context {
+        address: H160::repeat_byte(0xff),
+        caller: H160::repeat_byte(0xaa),
+        apparent_value: U256::zero(),
+    };
+    let mut handle = testing::CalibrationHandle::new(H160::repeat_byte(0xff), context)
+        .input(
+            EvmDataWriter::new_with_selector(DispatchAction::Set)
+                .write(U256::from(42u8))
+                .build(),
+        )
+        .with_subcall_result(ExitReason::Succeed(ExitSucceed::Returned), vec![1, 2, 3]);
+
+    DispatchPrecompile::execute(&mut handle).expect("call succeeds");
+    handle.record_log_costs_manual(1, 32).expect("no gas limit");
+    let (_, output) = subcall::call(
+        &mut handle,
+        H160::repeat_byte(0x11),
+        vec![],
+        U256::zero(),
+        None,
+    )
+    .expect("subcall succeeds");
+
+    assert_eq!(output, vec![1, 2, 3]);
+    assert_eq!(handle.subcalls, 1);
+    assert_eq!(handle.costs, vec![200, 375 + 375 + 8 * 32]);
+    assert_eq!(handle.record_count(), 2);
+    assert_eq!(handle.total_cost(), 200 + 1_006);
+}
+
+#[test]
+fn read_empty_array_at_end_of_input() {
+    // ABI encoding of `(uint256[], uint256[])` `([1], [])`, the offset of the empty array
+    // pointing to its length word, which is the last word of the input.
+    let input = hex!(
+        "0000000000000000000000000000000000000000000000000000000000000040
+		00000000000000000000000000000000000000000000000000000000000000a0
+		0000000000000000000000000000000000000000000000000000000000000001
+		0000000000000000000000000000000000000000000000000000000000000001
+		0000000000000000000000000000000000000000000000000000000000000000"
+    );
+
+    let mut reader = EvmDataReader::new(&input);
+    let first: Vec<U256> = reader.read().expect("to correctly parse Vec<U256>");
+    let second: Vec<U256> = reader.read().expect("to correctly parse empty Vec<U256>");
+    reader.expect_end().expect("no trailing data");
+
+    assert_eq!(first, vec![U256::one()]);
+    assert!(second.is_empty());
+    assert_eq!(
+        EvmDataWriter::new().write(first).write(second).build(),
+        input
+    );
+
+    // Without room for the length word the offset is still rejected.
+    let input = &input[..0xa0];
+    let mut reader = EvmDataReader::new(input);
+    reader
+        .read::<Vec<U256>>()
+        .expect("to correctly parse Vec<U256>");
+    assert_eq!(
+        reader.read::<Vec<U256>>(),
+        Err(revert_with_reason("pointer out of bounds"))
+    );
+}
+
+#[test]
+fn solidity_types_of_evm_data() {
+    assert_eq!(U256::solidity_type().as_deref(), Some("uint256"));
+    assert_eq!(u8::solidity_type().as_deref(), Some("uint8"));
+    assert_eq!(<FixedBytes<4>>::solidity_type().as_deref(), Some("bytes4"));
+    assert_eq!(
+        <Vec<(Address, Bytes)>>::solidity_type().as_deref(),
+        Some("(address,bytes)[]")
+    );
+    assert_eq!(<[bool; 2]>::solidity_type().as_deref(), Some("bool[2]"));
+    assert_eq!(
+        Batch::solidity_type().as_deref(),
+        Some("(uint256,(address,uint256,bytes))")
+    );
+}
+
+#[generate_function_selector]
+#[derive(Debug, PartialEq)]
+enum ReturnAction {
+    #[view]
+    #[returns("uint256,(address,uint256,bytes)")]
+    Order = "order(uint256)",
+    #[view]
+    #[returns("(address,bool)")]
+    Pair = "pair()",
+    #[view]
+    #[returns("address,bool")]
+    Check = "check()",
+    #[returns("uint256,string")]
+    Describe = "describe()",
+    #[returns("uint128")]
+    Small = "small()",
+    Touch = "touch()",
+}
+
+#[test]
+fn return_builder_writes_like_evm_data_writer() {
+    let output = ReturnAction::Order
+        .return_builder()
+        .write(U256::from(7u32))
+        .write(order())
+        .build();
+
+    assert_eq!(
+        output,
+        EvmDataWriter::new()
+            .write(U256::from(7u32))
+            .write(order())
+            .build()
+    );
+}
+
+#[test]
+fn return_builder_accepts_single_tuple() {
+    let value = (Address(H160::repeat_byte(0x11)), true);
+
+    assert_eq!(
+        ReturnAction::Pair.return_builder().write(value).build(),
+        EvmDataWriter::new().write(value).build()
+    );
+}
+
+#[test]
+fn return_builder_accepts_abi_compatible_types() {
+    let output = ReturnAction::Describe
+        .return_builder()
+        .write(7u128)
+        .write(Bytes::from("hello"))
+        .build();
+
+    assert_eq!(
+        output,
+        EvmDataWriter::new()
+            .write(U256::from(7u32))
+            .write(Bytes::from("hello"))
+            .build()
+    );
+}
+
+#[test]
+fn return_builder_without_returns() {
+    assert_eq!(ReturnAction::Touch.returns(), "");
+    assert!(ReturnAction::Touch.return_builder().build().is_empty());
+}
+
+#[test]
+fn selector_enum_lists_return_types() {
+    assert_eq!(ReturnAction::Check.returns(), "address,bool");
+    assert_eq!(ReturnAction::Pair.returns(), "(address,bool)");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "return value 1 should be `bool` but `uint256` was written")]
+fn return_builder_rejects_mismatching_type() {
+    ReturnAction::Check
+        .return_builder()
+        .write(Address(H160::repeat_byte(0x11)))
+        .write(U256::one())
+        .build();
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "return value 0 should be `uint128` but `uint256` was written")]
+fn return_builder_rejects_larger_integer() {
+    ReturnAction::Small
+        .return_builder()
+        .write(U256::one())
+        .build();
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "2 return values are declared but 1 were written")]
+fn return_builder_rejects_missing_value() {
+    ReturnAction::Check
+        .return_builder()
+        .write(Address(H160::repeat_byte(0x11)))
+        .build();
+}
+
+#[test]
+fn write_subcall_input_with_selector() {
+    // Calldata of `transfer(address,uint256)` `(0x1111111111111111111111111111111111111111, 1000)`.
+    let expected = hex!(
+        "a9059cbb
+		0000000000000000000000001111111111111111111111111111111111111111
+		00000000000000000000000000000000000000000000000000000000000003e8"
+    );
+
+    let input = EvmDataWriter::new_with_selector(0xa9059cbbu32)
+        .write(Address(H160::repeat_byte(0x11)))
+        .write(U256::from(1_000u32))
+        .build();
+
+    assert_eq!(input, expected);
+    assert_eq!(
+        EvmDataReader::peek_selector(&input).expect("to read selector"),
+        0xa9059cbb
+    );
+
+    let mut reader = EvmDataReader::new_skip_selector(&input).expect("to skip selector");
+    let to: Address = reader.read().expect("to correctly parse address");
+    let amount: U256 = reader.read().expect("to correctly parse U256");
+
+    assert_eq!(to, Address(H160::repeat_byte(0x11)));
+    assert_eq!(amount, U256::from(1_000u32));
+}
+
+/// `uint256[][][]` whose `items` items all point to the same middle array, whose `items` items
+/// all point to the same empty inner array.
+fn looping_pointers_input(items: usize) -> Vec<u8> {
+    let mut input = EvmDataWriter::new()
+        .write(U256::from(0x20))
+        .write(U256::from(items))
+        .build();
+    for _ in 0..items {
+        input.extend(EvmDataWriter::new().write(U256::from(items * 32)).build());
+    }
+    input.extend(EvmDataWriter::new().write(U256::from(items)).build());
+    for _ in 0..items {
+        input.extend(EvmDataWriter::new().write(U256::from(items * 32)).build());
+    }
+    input.extend(EvmDataWriter::new().write(U256::zero()).build());
+    input
+}
+
+#[test]
+fn read_rejects_pointers_looping_to_same_data() {
+    // 13 pointers are followed in an input of 10 words.
+    let input = looping_pointers_input(3);
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(
+        reader.read::<Vec<Vec<Vec<U256>>>>(),
+        Err(revert_with_reason("malformed nested encoding"))
+    );
+
+    // 7 pointers are followed in an input of 8 words, which is decoded as the limit only
+    // targets inputs growing the decoding work faster than their size.
+    let input = looping_pointers_input(2);
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(
+        reader.read::<Vec<Vec<Vec<U256>>>>(),
+        Ok(vec![vec![vec![]; 2]; 2])
+    );
+}
+
+#[test]
+fn copied_reader_does_not_share_pointer_budget() {
+    // Reading the input follows 7 pointers of the 8 allowed, reading it twice with the same
+    // budget would fail.
+    let input = looping_pointers_input(2);
+    let mut reader = EvmDataReader::new(&input);
+    let mut copy = reader;
+
+    assert_eq!(
+        copy.read::<Vec<Vec<Vec<U256>>>>(),
+        Ok(vec![vec![vec![]; 2]; 2])
+    );
+    assert_eq!(
+        reader.read::<Vec<Vec<Vec<U256>>>>(),
+        Ok(vec![vec![vec![]; 2]; 2])
+    );
+}
+
+#[test]
+fn read_rejects_pointers_nested_too_deep() {
+    let value = vec![vec![vec![U256::one()]]];
+    let input = EvmDataWriter::new().write(value.clone()).build();
+
+    let mut reader = EvmDataReader::new(&input);
+    assert_eq!(reader.read::<Vec<Vec<Vec<U256>>>>(), Ok(value));
+
+    let mut reader = EvmDataReader::new(&input).with_max_depth(2);
+    assert_eq!(
+        reader.read::<Vec<Vec<Vec<U256>>>>(),
+        Err(revert_with_reason("malformed nested encoding"))
+    );
+}
+
+#[test]
+fn measure_gas_returns_recorded_cost() {
+    let mut handle = mock_handle();
+    handle.record_cost(100).expect("enough gas");
+
+    let (value, used_gas) = handle
+        .measure_gas(|handle| {
+            handle.record_cost(1_234)?;
+            Ok(42u32)
+        })
+        .expect("enough gas");
+
+    assert_eq!(value, 42);
+    assert_eq!(used_gas, 1_234);
+    assert_eq!(handle.gas_used, 1_334);
+
+    assert_eq!(
+        handle.measure_gas(|_| -> EvmResult<()> { Err(revert("reverted")) }),
+        Err(revert("reverted"))
+    );
+}
+
+// ABI encoding of `(address, uint256, bytes)[]`
+// `[(0x1111111111111111111111111111111111111111, 1000, "hello"),
+// (0x2222222222222222222222222222222222222222, 2000, "world!")]`, as produced by ethers.js.
+const ETHERS_ORDER_ARRAY: [u8; 0x1c0] = hex!(
+    "0000000000000000000000000000000000000000000000000000000000000020
+	0000000000000000000000000000000000000000000000000000000000000002
+	0000000000000000000000000000000000000000000000000000000000000040
+	00000000000000000000000000000000000000000000000000000000000000e0
+	0000000000000000000000001111111111111111111111111111111111111111
+	00000000000000000000000000000000000000000000000000000000000003e8
+	0000000000000000000000000000000000000000000000000000000000000060
+	0000000000000000000000000000000000000000000000000000000000000005
+	68656c6c6f000000000000000000000000000000000000000000000000000000
+	0000000000000000000000002222222222222222222222222222222222222222
+	00000000000000000000000000000000000000000000000000000000000007d0
+	0000000000000000000000000000000000000000000000000000000000000060
+	0000000000000000000000000000000000000000000000000000000000000006
+	776f726c64210000000000000000000000000000000000000000000000000000"
+);
+
+#[test]
+fn derived_struct_array_matches_ethers() {
+    let orders = vec![
+        order(),
+        Order {
+            maker: H160::repeat_byte(0x22).into(),
+            amount: U256::from(2_000u32),
+            data: Bytes::from(&b"world!"[..]),
+        },
+    ];
+
+    // The offsets of the orders are relative to the start of the items, and the offsets of
+    // their `data` are relative to the start of each order.
+    let mut reader = EvmDataReader::new(&ETHERS_ORDER_ARRAY);
+    let parsed: Vec<Order> = reader.read().expect("to correctly parse Vec<Order>");
+    reader.expect_end().expect("no trailing data");
+    assert_eq!(parsed, orders);
+
+    assert_eq!(
+        EvmDataWriter::new().write(orders).build(),
+        ETHERS_ORDER_ARRAY
+    );
+}
+
+#[test]
+fn derived_static_struct_array_is_inline() {
+    let points = vec![
+        Point {
+            x: U256::from(1u8),
+            y: U256::from(2u8),
+        },
+        Point {
+            x: U256::from(3u8),
+            y: U256::from(4u8),
+        },
+    ];
+
+    // Static structs are packed inline in the array, without offsets.
+    let expected = hex!(
+        "0000000000000000000000000000000000000000000000000000000000000020
+		0000000000000000000000000000000000000000000000000000000000000002
+		0000000000000000000000000000000000000000000000000000000000000001
+		0000000000000000000000000000000000000000000000000000000000000002
+		0000000000000000000000000000000000000000000000000000000000000003
+		0000000000000000000000000000000000000000000000000000000000000004"
+    );
+
+    assert_eq!(EvmDataWriter::new().write(points.clone()).build(), expected);
+
+    let mut reader = EvmDataReader::new(&expected);
+    let parsed: Vec<Point> = reader.read().expect("to correctly parse Vec<Point>");
+    assert_eq!(parsed, points);
+}
+
+#[test]
+fn read_write_ufixed128x18() {
+    type UFixed128x18 = UFixed<128, 18>;
+
+    // 1.5 as a `ufixed128x18`.
+    let input = hex!("00000000000000000000000000000000000000000000000014d1120d7b160000");
+    let mut reader = EvmDataReader::new(&input);
+    let value: UFixed128x18 = reader.read().expect("to correctly parse ufixed128x18");
+
+    assert_eq!(value.mantissa(), U256::exp10(18) * 3 / 2);
+    assert_eq!(EvmDataWriter::new().write(value).build(), input);
+    assert_eq!(
+        UFixed128x18::solidity_type().as_deref(),
+        Some("ufixed128x18")
+    );
+
+    // The mantissa must fit in 128 bits.
+    let input = EvmDataWriter::new().write(U256::one() << 128).build();
+    assert_eq!(
+        EvmDataReader::new(&input).read::<UFixed128x18>(),
+        Err(revert_with_reason("value too large for ufixed128x18"))
+    );
+}
+
+#[test]
+fn ufixed_arithmetic_keeps_scale() {
+    type UFixed128x18 = UFixed<128, 18>;
+
+    let e18 = U256::exp10(18);
+    let one_and_half = UFixed128x18::from_mantissa(e18 * 3 / 2).expect("fits in 128 bits");
+    let two_and_half = UFixed128x18::from_mantissa(e18 * 5 / 2).expect("fits in 128 bits");
+    let value = |mantissa: U256| UFixed128x18::from_mantissa(mantissa).expect("fits in 128 bits");
+
+    assert_eq!(UFixed128x18::unit(), Ok(e18));
+    // 1.5 + 2.5 = 4
+    assert_eq!(one_and_half.checked_add(two_and_half), Ok(value(e18 * 4)));
+    // 2.5 - 1.5 = 1
+    assert_eq!(two_and_half.checked_sub(one_and_half), Ok(value(e18)));
+    // 1.5 * 2.5 = 3.75
+    assert_eq!(
+        one_and_half.checked_mul(two_and_half),
+        Ok(value(e18 * 375 / 100))
+    );
+    // 1.5 / 2.5 = 0.6
+    assert_eq!(
+        one_and_half.checked_div(two_and_half),
+        Ok(value(e18 * 6 / 10))
+    );
+
+    assert_eq!(
+        one_and_half.checked_sub(two_and_half),
+        Err(revert_with_reason("fixed value underflow"))
+    );
+    assert_eq!(
+        value(U256::from(u128::MAX)).checked_add(value(U256::one())),
+        Err(revert_with_reason("value too large for ufixed128x18"))
+    );
+    assert_eq!(
+        one_and_half.checked_div(UFixed128x18::default()),
+        Err(revert_with_reason("division by zero"))
+    );
+}
+
+#[test]
+fn encoding_matches_ethabi() {
+    use testing::EthabiToken;
+
+    let to = Address(H160::repeat_byte(0x11));
+    let amounts = vec![U256::one(), U256::MAX, U256::zero()];
+
+    let output = EvmDataWriter::new()
+        .write(to)
+        .write(amounts.clone())
+        .build();
+    assert_eq!(
+        output,
+        ethabi::encode(&[to.to_token(), amounts.clone().to_token()])
+    );
+
+    let tokens = ethabi::decode(
+        &[
+            ethabi::ParamType::Address,
+            ethabi::ParamType::Array(Box::new(ethabi::ParamType::Uint(256))),
+        ],
+        &output,
+    )
+    .expect("ethabi to decode output");
+    let mut tokens = tokens.into_iter();
+    assert_eq!(tokens.next().and_then(Address::from_token), Some(to));
+    assert_eq!(
+        tokens.next().and_then(Vec::<U256>::from_token),
+        Some(amounts)
+    );
+    assert_eq!(tokens.next(), None);
+
+    let data = vec![Bytes::from(&b"hello"[..]), Bytes(vec![])];
+    assert_eq!(
+        EvmDataWriter::new().write(data.clone()).build(),
+        ethabi::encode(&[data.to_token()])
+    );
+}
+
+/// Precompile holding its reentrancy guard while it calls the address in its input, if any.
+struct GuardedPrecompile;
+
+impl Precompile for GuardedPrecompile {
+    fn execute(handle: &mut impl PrecompileHandle) -> EvmResult<PrecompileOutput> {
+        let _guard = reentrancy::ReentrancyGuard::enter::<mock::Runtime>(handle)?;
+
+        if !handle.input().is_empty() {
+            let target: Address = EvmDataReader::new(handle.input()).read()?;
+            subcall::call(handle, target.0, vec![], U256::zero(), None)?;
+        }
+
+        Ok(succeed(vec![]))
+    }
+}
+
+/// Subcall handle running `GuardedPrecompile` at every called address.
+fn run_guarded_precompile(subcall: testing::Subcall) -> testing::SubcallOutput {
+    let mut handle = testing::MockHandle::new(subcall.address, subcall.context)
+        .gas_limit(subcall.target_gas.unwrap_or(u64::MAX))
+        .static_call(subcall.is_static)
+        .subcall_handle(run_guarded_precompile);
+
+    let (result, cost) = handle.run::<GuardedPrecompile>(subcall.input);
+    let (reason, output) = match result {
+        Ok(output) => (ExitReason::Succeed(ExitSucceed::Returned), output.output),
+        Err(PrecompileFailure::Revert { output, .. }) => {
+            (ExitReason::Revert(ExitRevert::Reverted), output)
+        }
+        Err(e) => panic!("unexpected failure {:?}", e),
+    };
+
+    testing::SubcallOutput {
+        reason,
+        output,
+        cost,
+        logs: vec![],
+    }
+}
+
+#[test]
+fn reentrancy_guard_rejects_reentering_the_same_precompile() {
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle().subcall_handle(run_guarded_precompile);
+        let call = |target: u8| {
+            EvmDataWriter::new()
+                .write(Address(H160::repeat_byte(target)))
+                .build()
+        };
+
+        // Calling another guarded precompile is allowed.
+        assert_eq!(
+            handle
+                .run::<GuardedPrecompile>(call(0x22))
+                .0
+                .map(|output| output.output),
+            Ok(vec![])
+        );
+
+        // Calling itself reverts, and the guard is cleared despite the revert.
+        assert_eq!(
+            handle
+                .run::<GuardedPrecompile>(call(0xff))
+                .0
+                .map(|output| output.output),
+            Err(revert_with_reason("reentrant call"))
+        );
+        assert_eq!(
+            handle
+                .run::<GuardedPrecompile>(call(0x22))
+                .0
+                .map(|output| output.output),
+            Ok(vec![])
+        );
+    })
+}
+
+#[test]
+fn reentrancy_guard_records_storage_costs() {
+    type Helper = RuntimeHelper<mock::Runtime>;
+
+    mock::new_test_ext().execute_with(|| {
+        let mut handle = mock_handle();
+        let (result, gas) = handle.run::<GuardedPrecompile>(vec![]);
+        assert!(result.is_ok());
+        assert_eq!(
+            gas,
+            Helper::db_read_gas_cost() + Helper::db_write_gas_cost_n(2)
+        );
+
+        // The flag is only checked in static calls.
+        let mut handle = mock_handle().static_call(true);
+        let (result, gas) = handle.run::<GuardedPrecompile>(vec![]);
+        assert!(result.is_ok());
+        assert_eq!(gas, Helper::db_read_gas_cost());
+
+        // Nothing is written if the gas runs out.
+        let mut handle = mock_handle().gas_limit(Helper::db_read_gas_cost());
+        assert_eq!(
+            handle
+                .run::<GuardedPrecompile>(vec![])
+                .0
+                .map(|output| output.output),
+            Err(out_of_gas())
+        );
+        let mut handle = mock_handle();
+        assert!(handle.run::<GuardedPrecompile>(vec![]).0.is_ok());
+    })
+}
+
+#[test]
+fn pretty_writer_and_reader() {
+    let writer = EvmDataWriter::new_with_selector(0xa9059cbbu32)
+        .write(Address(H160::repeat_byte(0x11)))
+        .write(vec![U256::from(0x2au8)]);
+
+    assert_eq!(
+        writer.pretty(),
+        "0xa9059cbb  // selector
+0x0000000000000000000000001111111111111111111111111111111111111111  // word 0
+0x0000000000000000000000000000000000000000000000000000000000000040  // word 1
+0x0000000000000000000000000000000000000000000000000000000000000001  // word 2
+0x000000000000000000000000000000000000000000000000000000000000002a  // word 3"
+    );
+
+    let input = EvmDataWriter::new()
+        .write(U256::one())
+        .write(true)
+        .write(H256::repeat_byte(0xff))
+        .build();
+    let mut reader = EvmDataReader::new(&input);
+    reader.read::<U256>().expect("to correctly parse U256");
+
+    assert_eq!(
+        reader.pretty(),
+        "0x0000000000000000000000000000000000000000000000000000000000000001  // word 0
+0x0000000000000000000000000000000000000000000000000000000000000001  // word 1 <- cursor
+0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff  // word 2"
+    );
+
+    reader.skip(2).expect("to skip words");
+    assert!(reader.pretty().ends_with("// word 2\n// end <- cursor"));
+}